@@ -0,0 +1,129 @@
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+use rustlet::font::{read_font_file, Font};
+use rustlet::{art_lines, art_stream};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts every allocation made, so the cost of a render can be
+/// reported in allocations as well as time.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const PARAGRAPH: &str = "It was the best of times, it was the worst of times, it was \
+the age of wisdom, it was the age of foolishness, it was the epoch of belief, it was \
+the epoch of incredulity, it was the season of Light, it was the season of Darkness, \
+it was the spring of hope, it was the winter of despair, we had everything before us, \
+we had nothing before us, we were all going direct to Heaven, we were all going \
+direct the other way.";
+
+const SHORT: &str = "Hello, World!";
+
+/// Fonts covering each horizontal layout mode
+const FONTS: [(&str, &str); 10] = [
+    ("kerning", "banner"),
+    ("kerning", "lean"),
+    ("smushing", "standard"),
+    ("smushing", "slant"),
+    ("smushing", "small"),
+    ("smushing", "big"),
+    ("smushing", "ivrit"),
+    ("overlap", "mini"),
+    ("full-width", "term"),
+    ("full-width", "basic"),
+];
+
+fn load(name: &str) -> Font {
+    read_font_file(format!("fonts/{}.flf", name)).expect("Font not read")
+}
+
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Prints how many allocations it takes to lay out the paragraph in
+/// every bundled font.
+fn report_allocations() {
+    let mut paths: Vec<_> = fs::read_dir("fonts")
+        .expect("fonts directory")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    paths.sort();
+    let mut total = 0;
+    for path in paths {
+        let font = read_font_file(&path).expect("Font not read");
+        let count = allocations(|| {
+            black_box(art_lines(PARAGRAPH, &font, &font.settings, 80));
+        });
+        println!(
+            "allocations {:>20}: {}",
+            path.file_stem().unwrap().to_string_lossy(),
+            count
+        );
+        total += count;
+    }
+    println!("allocations {:>20}: {}", "total", total);
+}
+
+fn messages(c: &mut Criterion) {
+    for (group_name, message) in [("short", SHORT), ("long", PARAGRAPH)] {
+        let mut group = c.benchmark_group(group_name);
+        group.throughput(Throughput::Elements(message.chars().count() as u64));
+        for (mode, name) in FONTS {
+            let font = load(name);
+            let id = BenchmarkId::new(mode, name);
+            group.bench_with_input(id, &font, |b, font| {
+                b.iter(|| art_lines(black_box(message), font, &font.settings, 80))
+            });
+        }
+        group.finish();
+    }
+}
+
+/// Layout alone, on plain text: no output formatting, no character
+/// translation, just characters in and art lines out.
+fn throughput(c: &mut Criterion) {
+    let message = PARAGRAPH.repeat(20);
+    let mut group = c.benchmark_group("throughput");
+    group.throughput(Throughput::Elements(message.chars().count() as u64));
+    for (mode, name) in FONTS {
+        let font = load(name);
+        let id = BenchmarkId::new(mode, name);
+        group.bench_with_input(id, &font, |b, font| {
+            b.iter(|| art_stream(black_box(message.chars()), font, &font.settings, 80).count())
+        });
+    }
+    group.finish();
+}
+
+fn main() {
+    report_allocations();
+
+    let mut criterion = Criterion::default().configure_from_args();
+    messages(&mut criterion);
+    throughput(&mut criterion);
+    criterion.final_summary();
+}
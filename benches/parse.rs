@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rustlet::read_font;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Every bundled font, read up front so only parsing is measured
+fn fonts() -> Vec<(String, String)> {
+    let mut paths: Vec<PathBuf> = fs::read_dir("fonts")
+        .expect("fonts directory")
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let data = fs::read(&path).expect("Font not read");
+            (name, String::from_utf8_lossy(&data).into_owned())
+        })
+        .collect()
+}
+
+fn read_font_each(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_font");
+    group
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(200))
+        .measurement_time(Duration::from_millis(500));
+    for (name, data) in fonts() {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(&name), &data, |b, data| {
+            b.iter(|| read_font(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn read_font_all(c: &mut Criterion) {
+    let fonts = fonts();
+    let bytes: usize = fonts.iter().map(|(_, data)| data.len()).sum();
+    let mut group = c.benchmark_group("read_font_all");
+    group.throughput(Throughput::Bytes(bytes as u64));
+    group.bench_function("bundled", |b| {
+        b.iter(|| {
+            for (_, data) in &fonts {
+                black_box(read_font(black_box(data)).ok());
+            }
+        })
+    });
+    // The same fonts, loaded from a binary cache instead: all of them,
+    // and just one
+    #[cfg(feature = "serde")]
+    {
+        use rustlet::cache::{read_font_dir, write_cache, FontCache};
+        let collection = read_font_dir("fonts").expect("Fonts not read");
+        let mut cache = vec![];
+        write_cache(&collection, &mut cache).expect("Cache not written");
+        group.bench_function("cache", |b| {
+            b.iter(|| {
+                let cache = FontCache::read(black_box(&cache[..])).unwrap();
+                black_box(cache.fonts().ok())
+            })
+        });
+        group.bench_function("cache_one", |b| {
+            b.iter(|| {
+                let cache = FontCache::read(black_box(&cache[..])).unwrap();
+                black_box(cache.get("standard").ok())
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read_font_each, read_font_all);
+criterion_main!(benches);
@@ -0,0 +1,106 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustlet::scale::Factor;
+use rustlet::settings::{Settings, SmushMode};
+use rustlet::text::ArtOutput;
+use rustlet::{Overflow, Pixelate, Scale, Wrap};
+
+const STANDARD: &str = include_str!("../../fonts/standard.flf");
+
+/// The layout options the fuzzer picked, drawn from the bits of a
+/// number
+struct Options {
+    deutsch: bool,
+    bidi: bool,
+    overflow: Overflow,
+    wrap: Wrap,
+    scale: Option<Scale>,
+    pixelate: Option<Pixelate>,
+}
+
+impl Options {
+    fn from_bits(bits: u32) -> Self {
+        let field = |shift: u32, size: u32| (bits >> shift) % size;
+        // small factors, so that the art stays small enough to fuzz fast
+        let factor = |shift: u32| {
+            Factor::new(
+                field(shift, 4) as usize + 1,
+                field(shift + 2, 4) as usize + 1,
+            )
+            .unwrap()
+        };
+        Options {
+            deutsch: field(0, 2) == 1,
+            bidi: field(1, 2) == 1,
+            overflow: [
+                Overflow::Break,
+                Overflow::Hyphenate,
+                Overflow::Shrink,
+                Overflow::Truncate,
+            ][field(2, 4) as usize],
+            wrap: [Wrap::Greedy, Wrap::MinimumRaggedness][field(4, 2) as usize],
+            scale: (field(5, 2) == 1).then(|| Scale {
+                across: factor(6),
+                down: factor(10),
+            }),
+            pixelate: [
+                None,
+                Some(Pixelate::HalfBlocks),
+                Some(Pixelate::Quadrants),
+                Some(Pixelate::Braille),
+            ][field(14, 4) as usize],
+        }
+    }
+}
+
+fuzz_target!(|input: (Option<String>, String, u16, u32, bool, u32)| {
+    let (font_data, message, width, smushmode, right2left, options) = input;
+    let font = match font_data {
+        Some(data) => match rustlet::read_font(&data) {
+            Ok(font) => font,
+            Err(_) => return,
+        },
+        None => rustlet::read_font(STANDARD).unwrap(),
+    };
+    let _ = rustlet::art_lines(&message, &font, &font.settings, width as usize);
+
+    // The same glyphs under arbitrary layout settings
+    let settings = Settings {
+        smushmode: SmushMode::from(smushmode),
+        right2left,
+        ..font.settings
+    };
+    let _ = rustlet::art_lines(&message, &font, &settings, width as usize);
+
+    // and arbitrary layout options, at widths from none at all to the
+    // largest there are
+    let max_width = match (width, options >> 31) {
+        (0, _) => None,
+        (width, 1) => Some(usize::MAX - width as usize),
+        (width, _) => Some(width as usize),
+    };
+    let Options {
+        deutsch,
+        bidi,
+        overflow,
+        wrap,
+        scale,
+        pixelate,
+    } = Options::from_bits(options);
+    let _ = ArtOutput::build(&message, &font, &settings, max_width)
+        .deutsch(deutsch)
+        .bidi(bidi)
+        .overflow(overflow)
+        .wrap(wrap)
+        .scale(scale)
+        .pixelate(pixelate)
+        .render();
+    for _ in rustlet::art_stream(message.chars(), &font, &settings, max_width)
+        .deutsch(deutsch)
+        .overflow(overflow)
+        .wrap(wrap)
+        .scale(scale)
+        .pixelate(pixelate)
+    {}
+});
@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustlet::bitmap::{read_bitmap_font, Pixels};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(bitmap) = read_bitmap_font(data) {
+        let _ = bitmap.to_font(Pixels::default());
+        let _ = bitmap.to_font(Pixels::HalfBlocks);
+    }
+});
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustlet::cache::FontCache;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(cache) = FontCache::read(data) else {
+        return;
+    };
+    for name in cache.names() {
+        // whatever a cache holds, the fonts read from it must be usable
+        if let Ok(font) = cache.get(name) {
+            let _ = rustlet::font::write_font(&font);
+            let _ = rustlet::art_lines("Hello", &font, &font.settings, 80);
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustlet::bitmap::{read_glyph_sheet, Pixels};
+
+fuzz_target!(|input: (u8, u8, String, &[u8])| {
+    let (width, height, chars, image) = input;
+    let cell = (width as usize, height as usize);
+    if let Ok(sheet) = read_glyph_sheet(image, cell, &chars) {
+        let _ = sheet.to_font(Pixels::default());
+    }
+});
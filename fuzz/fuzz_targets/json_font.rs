@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let data = String::from_utf8_lossy(data);
+    if let Ok(font) = rustlet::json::read_font(&data) {
+        let _ = rustlet::json::write_font(&font);
+    }
+});
@@ -0,0 +1,75 @@
+use unicode_bidi::BidiInfo;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// One paragraph of input, reordered for display
+pub(crate) struct Paragraph {
+    /// whether the paragraph's base direction is right-to-left
+    pub(crate) rtl: bool,
+    /// the paragraph's characters with their indices in the input, in
+    /// the order they should be fed to the layout: left to right for a
+    /// left-to-right paragraph, right to left otherwise
+    pub(crate) chars: Vec<(usize, char)>,
+}
+
+/// Mirrors paired punctuation drawn in a right-to-left run
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        _ => c,
+    }
+}
+
+/// Splits `input` into paragraphs and reorders each with the Unicode
+/// Bidirectional Algorithm. A paragraph is reordered as a whole, as if
+/// it were a single line, before it is broken into art lines.
+pub(crate) fn paragraphs(input: &str) -> Vec<Paragraph> {
+    let info = BidiInfo::new(input, None);
+    // byte offset -> character index
+    let mut indices = vec![0; input.len()];
+    for (index, (offset, _)) in input.char_indices().enumerate() {
+        indices[offset] = index;
+    }
+
+    let indices = &indices;
+    info.paragraphs
+        .iter()
+        .map(|para| {
+            let (levels, runs) = info.visual_runs(para, para.range.clone());
+            // Reordering moves whole grapheme clusters, keeping combining
+            // marks after their base character
+            let mut clusters = vec![];
+            for run in runs {
+                let start = clusters.len();
+                for (offset, grapheme) in input[run.clone()].grapheme_indices(true) {
+                    let offset = run.start + offset;
+                    clusters.push((offset, grapheme));
+                }
+                if levels[run.start].is_rtl() {
+                    clusters[start..].reverse();
+                }
+            }
+            let rtl = para.level.is_rtl();
+            if rtl {
+                clusters.reverse();
+            }
+            let chars = clusters
+                .into_iter()
+                .flat_map(|(offset, grapheme)| {
+                    let mirrored = levels[offset].is_rtl();
+                    grapheme.char_indices().map(move |(i, c)| {
+                        let c = if mirrored { mirror(c) } else { c };
+                        (indices[offset + i], c)
+                    })
+                })
+                .collect();
+            Paragraph { rtl, chars }
+        })
+        .collect()
+}
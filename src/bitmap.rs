@@ -0,0 +1,754 @@
+//! Bitmap fonts, such as X11's BDF fonts, the Linux console's PSF fonts
+//! and glyphs drawn in an image, made into FIGfonts by drawing each
+//! pixel as a sub-character.
+//!
+//! Glyphs are trimmed to their ink and followed by a column of
+//! hardblanks, and the FIGfont kerns, so that characters end up one
+//! column apart however the bitmap font spaced them. Glyphs with no ink,
+//! such as space, keep their width as hardblanks.
+
+use crate::font::{art_row, comment_lines, Font, FontError, REQUIRED_CHARACTERS};
+use crate::netpbm::read_image;
+use crate::settings::{Settings, SmushMode};
+use crate::text::Text;
+use nom::{
+    bytes::complete::tag,
+    number::complete::{le_u32, u8 as byte},
+    sequence::{preceded, tuple},
+    IResult,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+use std::str::FromStr;
+
+const PSF1_MAGIC: &[u8] = &[0x36, 0x04];
+const PSF2_MAGIC: &[u8] = &[0x72, 0xb5, 0x4a, 0x86];
+/// The most pixels across or down a glyph may have
+const MAX_SIZE: usize = 1024;
+/// The most pixels a whole font may have, so that a font whose header
+/// claims to be huge is turned away before it is drawn
+const MAX_PIXELS: usize = 1 << 26;
+
+/// How to draw pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pixels {
+    /// one sub-character per pixel: `dark` for inked pixels, such as
+    /// `#` or `█`, and `light` for the rest
+    Chars { dark: char, light: char },
+    /// two rows of pixels to a line, drawn with the half blocks `▀`, `▄`
+    /// and `█`, so that the art is half as high
+    HalfBlocks,
+}
+
+impl Default for Pixels {
+    fn default() -> Self {
+        Pixels::Chars {
+            dark: '#',
+            light: ' ',
+        }
+    }
+}
+
+/// `half` for half blocks, or the characters to draw pixels with: the
+/// dark one, then optionally the light one, which is otherwise a space
+impl FromStr for Pixels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            _ if s == "half" => Ok(Pixels::HalfBlocks),
+            (Some(dark), light, None) => Ok(Pixels::Chars {
+                dark,
+                light: light.unwrap_or(' '),
+            }),
+            _ => Err(format!(
+                "expected one or two characters, or \"half\", not {:?}",
+                s
+            )),
+        }
+    }
+}
+
+impl Pixels {
+    fn draws_with(&self, c: char) -> bool {
+        match *self {
+            Pixels::Chars { dark, light } => c == dark || c == light,
+            Pixels::HalfBlocks => "▀▄█ ".contains(c),
+        }
+    }
+}
+
+/// One glyph's pixels, in a cell as high as its font
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glyph {
+    /// how far along the next glyph starts, in pixels
+    pub width: usize,
+    /// each row of pixels from the top, `width` long, true where inked
+    pub pixels: Vec<Vec<bool>>,
+}
+
+impl Glyph {
+    fn blank(width: usize, height: usize) -> Glyph {
+        Glyph {
+            width,
+            pixels: vec![vec![false; width]; height],
+        }
+    }
+
+    fn set(&mut self, row: usize, column: usize) {
+        if let Some(pixel) = self.pixels.get_mut(row).and_then(|r| r.get_mut(column)) {
+            *pixel = true;
+        }
+    }
+
+    /// The columns from the first with ink to the last, if any have ink
+    fn ink(&self) -> Option<(usize, usize)> {
+        let inked = |column: &usize| self.pixels.iter().any(|row| row[*column]);
+        let first = (0..self.width).find(inked)?;
+        let last = (0..self.width).rev().find(inked)?;
+        Some((first, last))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitmapFont {
+    /// what the font calls itself, if anything, for the FIGfont comment
+    pub name: String,
+    /// rows of pixels in every glyph
+    pub height: usize,
+    /// rows of pixels above the baseline
+    pub ascent: usize,
+    pub glyphs: BTreeMap<char, Glyph>,
+}
+
+impl BitmapFont {
+    /// Draws the font as a FIGfont. Required characters the bitmap font
+    /// lacks are left blank, except for space. Fails if the font is no
+    /// pixels high.
+    pub fn to_font(&self, pixels: Pixels) -> Result<Font, FontError> {
+        let hardblank = ['$', '%', '&']
+            .into_iter()
+            .find(|&c| !pixels.draws_with(c))
+            .expect("pixels are drawn with at most two characters");
+        let halve = |rows: usize| match pixels {
+            Pixels::Chars { .. } => rows,
+            Pixels::HalfBlocks => rows.div_ceil(2),
+        };
+        let height = halve(self.height);
+        let baseline = halve(self.ascent);
+
+        // a space half as wide as the font is high, if it has none
+        let space = Glyph::blank((self.height / 2).max(1), self.height);
+        let space = (!self.glyphs.contains_key(&' ')).then_some((&' ', &space));
+
+        let mut characters = HashMap::new();
+        for (&c, glyph) in self.glyphs.iter().chain(space) {
+            if c.is_control() {
+                continue;
+            }
+            let art = draw(glyph, pixels, hardblank)
+                .into_iter()
+                .map(art_row)
+                .collect();
+            let text = Text {
+                text: c.to_string(),
+                art,
+                spans: vec![],
+                baseline,
+            };
+            characters.insert(c, text);
+        }
+        for c in REQUIRED_CHARACTERS {
+            characters.entry(c).or_insert_with(|| Text {
+                text: c.to_string(),
+                baseline,
+                ..Text::empty_of_height(height as u32)
+            });
+        }
+
+        let widest = characters
+            .values()
+            .flat_map(|text| text.art.iter())
+            .map(|row| row.len())
+            .max()
+            .unwrap_or(0);
+        let settings = Settings {
+            hardblank,
+            charheight: height as u32,
+            baseline: baseline as u32,
+            maxlength: widest as u32 + 2,
+            commentlines: comment_lines(&self.name).len() as u32,
+            right2left: false,
+            smushmode: SmushMode::KERN,
+        };
+        Font::from_parts(settings, self.name.clone(), characters, HashMap::new())
+    }
+}
+
+/// Draws a glyph's ink, then a column of hardblanks to keep it apart
+/// from the next
+fn draw(glyph: &Glyph, pixels: Pixels, hardblank: char) -> Vec<Vec<char>> {
+    let Some((first, last)) = glyph.ink() else {
+        let width = glyph.width.max(1);
+        let height = match pixels {
+            Pixels::Chars { .. } => glyph.pixels.len(),
+            Pixels::HalfBlocks => glyph.pixels.len().div_ceil(2),
+        };
+        return vec![vec![hardblank; width]; height];
+    };
+    let inked = |row: &[bool]| row[first..=last].to_vec();
+    let mut lines: Vec<Vec<char>> = match pixels {
+        Pixels::Chars { dark, light } => glyph
+            .pixels
+            .iter()
+            .map(|row| {
+                inked(row)
+                    .into_iter()
+                    .map(|on| if on { dark } else { light })
+                    .collect()
+            })
+            .collect(),
+        Pixels::HalfBlocks => glyph
+            .pixels
+            .chunks(2)
+            .map(|rows| {
+                let top = inked(&rows[0]);
+                let bottom = rows.get(1).map_or(vec![false; top.len()], |row| inked(row));
+                top.into_iter()
+                    .zip(bottom)
+                    .map(|pair| match pair {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect(),
+    };
+    for line in &mut lines {
+        line.push(hardblank);
+    }
+    lines
+}
+
+fn syntax(message: &str) -> FontError {
+    FontError::Syntax(message.to_string())
+}
+
+/// Checks that `count` glyphs `width` by `height` pixels are not too
+/// many to draw
+fn check_size(width: usize, height: usize, count: usize) -> Result<(), FontError> {
+    let too_large = width > MAX_SIZE
+        || height > MAX_SIZE
+        || (width * height).saturating_mul(count) > MAX_PIXELS;
+    if too_large {
+        return Err(syntax("font is too large"));
+    }
+    Ok(())
+}
+
+/// Reads a row of a BDF bitmap, two hex digits to a byte
+fn hex_bytes(hex: &str) -> Result<Vec<u8>, FontError> {
+    let digit = |c: u8| {
+        (c as char)
+            .to_digit(16)
+            .ok_or_else(|| syntax("BDF: bitmap is not hex"))
+    };
+    hex.as_bytes()
+        .chunks_exact(2)
+        .map(|pair| Ok((digit(pair[0])? << 4 | digit(pair[1])?) as u8))
+        .collect()
+}
+
+fn numbers<'a, I: Iterator<Item = &'a str>>(words: I) -> Result<Vec<i32>, FontError> {
+    words
+        .map(|word| word.parse().map_err(|_| syntax("BDF: expected a number")))
+        .collect()
+}
+
+/// What a BDF character says about itself
+struct BdfChar {
+    code: Option<char>,
+    /// DWIDTH's x
+    advance: Option<i32>,
+    /// BBX: width, height, x and y offset from the origin
+    bounds: [i32; 4],
+    /// each row of the bitmap, as hex
+    bitmap: Vec<String>,
+}
+
+fn read_bdf_char<'a, I: Iterator<Item = &'a str>>(lines: &mut I) -> Result<BdfChar, FontError> {
+    let mut code = None;
+    let mut advance = None;
+    let mut bounds = None;
+    while let Some(line) = lines.next() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("ENCODING") => {
+                // -1 is for glyphs outside the encoding, which may be
+                // given a code of their own after it
+                let codes = numbers(words)?;
+                code = codes
+                    .iter()
+                    .rev()
+                    .find(|code| **code >= 0)
+                    .and_then(|code| char::from_u32(*code as u32));
+            }
+            Some("DWIDTH") => advance = numbers(words)?.first().copied(),
+            Some("BBX") => {
+                bounds = Some(
+                    numbers(words)?
+                        .try_into()
+                        .map_err(|_| syntax("BDF: BBX needs four numbers"))?,
+                )
+            }
+            Some("BITMAP") => {
+                let bounds = bounds.ok_or_else(|| syntax("BDF: character without a BBX"))?;
+                let mut bitmap = vec![];
+                for line in lines.by_ref() {
+                    if line.trim() == "ENDCHAR" {
+                        return Ok(BdfChar {
+                            code,
+                            advance,
+                            bounds,
+                            bitmap,
+                        });
+                    }
+                    bitmap.push(line.trim().to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+    Err(syntax("BDF: character cut short"))
+}
+
+/// Reads an X11 BDF font. Encodings are taken to be Unicode, which
+/// they are for ISO 10646 fonts and, up to 255, for ISO 8859-1 ones.
+pub fn read_bdf(bdf: &str) -> Result<BitmapFont, FontError> {
+    let mut lines = bdf.lines();
+    if !lines
+        .next()
+        .is_some_and(|line| line.starts_with("STARTFONT"))
+    {
+        return Err(syntax("not a BDF font"));
+    }
+    let mut name = String::new();
+    let mut font_bounds = None;
+    let mut ascent = None;
+    let mut descent = None;
+    let mut chars = vec![];
+    while let Some(line) = lines.next() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("FONT") => name = words.collect::<Vec<_>>().join(" "),
+            Some("FONTBOUNDINGBOX") => font_bounds = Some(numbers(words)?),
+            Some("FONT_ASCENT") => ascent = numbers(words)?.first().copied(),
+            Some("FONT_DESCENT") => descent = numbers(words)?.first().copied(),
+            Some("STARTCHAR") => chars.push(read_bdf_char(&mut lines)?),
+            _ => (),
+        }
+    }
+
+    let (ascent, descent) = match (ascent, descent, font_bounds.as_deref()) {
+        (Some(ascent), Some(descent), _) => (ascent, descent),
+        (_, _, Some(&[_, height, _, y])) => (height + y, -y),
+        _ => return Err(syntax("BDF: font has no size")),
+    };
+    let too_large = || syntax("font is too large");
+    let height = ascent.checked_add(descent).ok_or_else(too_large)?.max(0) as usize;
+    check_size(0, height, 0)?;
+    let ascent_rows = ascent.clamp(0, height as i32) as usize;
+
+    let mut glyphs = BTreeMap::new();
+    for ch in chars {
+        let Some(code) = ch.code else {
+            continue;
+        };
+        let [width, rows, x, y] = ch.bounds;
+        let right = x.checked_add(width).ok_or_else(too_large)?;
+        let advance = ch.advance.unwrap_or(right).max(right).max(0) as usize;
+        check_size(advance, height, glyphs.len() + 1)?;
+        let mut glyph = Glyph::blank(advance, height);
+        // the bitmap's top row is this far below the top of the cell
+        let top = y
+            .checked_add(rows)
+            .and_then(|bottom| ascent.checked_sub(bottom))
+            .ok_or_else(too_large)?;
+        for (row, hex) in ch.bitmap.iter().enumerate() {
+            let bytes = hex_bytes(hex)?;
+            for column in 0..(width.max(0) as usize).min(bytes.len() * 8) {
+                let on = bytes[column / 8] & (0x80 >> (column % 8)) != 0;
+                let (cell_row, cell_column) = (top as i64 + row as i64, x as i64 + column as i64);
+                if on && cell_row >= 0 && cell_column >= 0 {
+                    glyph.set(cell_row as usize, cell_column as usize);
+                }
+            }
+        }
+        glyphs.insert(code, glyph);
+    }
+    Ok(BitmapFont {
+        name,
+        height,
+        ascent: ascent_rows,
+        glyphs,
+    })
+}
+
+/// PSF1's header: mode, then bytes per glyph
+fn psf1_header(input: &[u8]) -> IResult<&[u8], (u8, u8)> {
+    preceded(tag(PSF1_MAGIC), tuple((byte, byte)))(input)
+}
+
+/// PSF2's header after the magic: version, header size, flags, number
+/// of glyphs, bytes per glyph, height and width
+type Psf2Header = (u32, u32, u32, u32, u32, u32, u32);
+
+fn psf2_header(input: &[u8]) -> IResult<&[u8], Psf2Header> {
+    preceded(
+        tag(PSF2_MAGIC),
+        tuple((le_u32, le_u32, le_u32, le_u32, le_u32, le_u32, le_u32)),
+    )(input)
+}
+
+/// Reads the code points of each glyph from a PSF Unicode table, where
+/// `next` reads one code point or separator. Sequences of several code
+/// points, which come after the single ones, are skipped.
+fn unicode_table<F>(count: usize, mut next: F) -> Vec<Vec<char>>
+where
+    F: FnMut() -> Option<Separated>,
+{
+    let mut table = vec![];
+    for _ in 0..count {
+        let mut codes = vec![];
+        let mut in_sequences = false;
+        loop {
+            match next() {
+                None => return table,
+                Some(Separated::End) => break,
+                Some(Separated::Sequences) => in_sequences = true,
+                Some(Separated::Code(c)) if !in_sequences => codes.push(c),
+                Some(Separated::Code(_)) => (),
+            }
+        }
+        table.push(codes);
+    }
+    table
+}
+
+enum Separated {
+    Code(char),
+    /// the rest of the glyph's entry is sequences
+    Sequences,
+    /// the end of the glyph's entry
+    End,
+}
+
+/// Reads a Linux console PSF font, version 1 or 2. Without a Unicode
+/// table, each glyph is taken to be the Latin-1 character of its index.
+pub fn read_psf(psf: &[u8]) -> Result<BitmapFont, FontError> {
+    let truncated = || syntax("PSF: font cut short");
+    let (glyph_data, count, glyph_size, height, width, table) = if let Ok((rest, (mode, size))) =
+        psf1_header(psf)
+    {
+        let count = if mode & 1 != 0 { 512 } else { 256 };
+        let size = size as usize;
+        let table = rest.get(count * size..).filter(|_| mode & 6 != 0);
+        (rest, count, size, size, 8, table.map(|t| (t, 1)))
+    } else if let Ok((_, (_, header_size, flags, count, size, height, width))) = psf2_header(psf) {
+        let (count, size) = (count as usize, size as usize);
+        let rest = psf.get(header_size as usize..).ok_or_else(truncated)?;
+        let glyphs_size = count.checked_mul(size).ok_or_else(truncated)?;
+        let table = rest.get(glyphs_size..).filter(|_| flags & 1 != 0);
+        let (height, width) = (height as usize, width as usize);
+        (rest, count, size, height, width, table.map(|t| (t, 2)))
+    } else {
+        return Err(syntax("not a PSF font"));
+    };
+
+    let codes = match table {
+        None => (0..count.min(256))
+            .map(|i| vec![char::from(i as u8)])
+            .collect(),
+        // PSF1: little-endian UCS-2, 0xFFFE and 0xFFFF as separators
+        Some((table, 1)) => {
+            let mut words = table
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]));
+            unicode_table(count, || {
+                Some(match words.next()? {
+                    0xFFFF => Separated::End,
+                    0xFFFE => Separated::Sequences,
+                    word => Separated::Code(char::from_u32(word as u32)?),
+                })
+            })
+        }
+        // PSF2: UTF-8, 0xFE and 0xFF as separators
+        Some((table, _)) => {
+            let mut rest = table;
+            unicode_table(count, || {
+                let (&first, tail) = rest.split_first()?;
+                match first {
+                    0xFF => {
+                        rest = tail;
+                        Some(Separated::End)
+                    }
+                    0xFE => {
+                        rest = tail;
+                        Some(Separated::Sequences)
+                    }
+                    _ => {
+                        let len = match first {
+                            0xF0.. => 4,
+                            0xE0.. => 3,
+                            0xC0.. => 2,
+                            _ => 1,
+                        };
+                        let encoded = rest.get(..len)?;
+                        rest = &rest[len..];
+                        let c = std::str::from_utf8(encoded).ok()?.chars().next()?;
+                        Some(Separated::Code(c))
+                    }
+                }
+            })
+        }
+    };
+
+    if width == 0 || height == 0 {
+        return Err(syntax("PSF: glyphs are no pixels across"));
+    }
+    let row_size = width.div_ceil(8);
+    if row_size
+        .checked_mul(height)
+        .is_none_or(|size| size > glyph_size)
+    {
+        return Err(syntax("PSF: glyphs are too small for their rows"));
+    }
+    check_size(width, height, count)?;
+    let mut glyphs = BTreeMap::new();
+    for (index, codes) in codes.into_iter().enumerate() {
+        let data = glyph_data
+            .get(index * glyph_size..(index + 1) * glyph_size)
+            .ok_or_else(truncated)?;
+        let mut glyph = Glyph::blank(width, height);
+        for (row, bytes) in data.chunks(row_size).take(height).enumerate() {
+            for column in 0..width {
+                if bytes[column / 8] & (0x80 >> (column % 8)) != 0 {
+                    glyph.set(row, column);
+                }
+            }
+        }
+        for c in codes {
+            glyphs.entry(c).or_insert_with(|| glyph.clone());
+        }
+    }
+
+    // PSF doesn't say where the baseline is
+    Ok(BitmapFont {
+        name: String::new(),
+        height,
+        ascent: guess_ascent(&glyphs, height),
+        glyphs,
+    })
+}
+
+/// Puts the baseline under the capital letters, for fonts that don't
+/// say where it is
+fn guess_ascent(glyphs: &BTreeMap<char, Glyph>, height: usize) -> usize {
+    ['H', 'A', 'X']
+        .iter()
+        .filter_map(|c| glyphs.get(c))
+        .find_map(|glyph| glyph.pixels.iter().rposition(|row| row.contains(&true)))
+        .map_or(height, |bottom| bottom + 1)
+}
+
+/// Reads a sheet of glyphs drawn in a PBM or PGM image, divided into a
+/// grid of equal cells `cell` pixels wide and high. The cells hold the
+/// glyphs for `chars` in order, left to right and then top to bottom;
+/// any cells left over are ignored. Pixels nearer black than white are
+/// ink.
+pub fn read_glyph_sheet(
+    data: &[u8],
+    cell: (usize, usize),
+    chars: &str,
+) -> Result<BitmapFont, FontError> {
+    let (width, height) = cell;
+    if width == 0 || height == 0 {
+        return Err(syntax("glyph sheet cells must be at least a pixel across"));
+    }
+    let image = read_image(data)?;
+    let columns = image.first().map_or(0, Vec::len) / width;
+    let cells = columns * (image.len() / height);
+    let wanted = chars.chars().count();
+    if wanted > cells {
+        return Err(FontError::Syntax(format!(
+            "glyph sheet has {} cells, too few for {} characters",
+            cells, wanted
+        )));
+    }
+
+    let mut glyphs = BTreeMap::new();
+    for (index, c) in chars.chars().enumerate() {
+        let (top, left) = (index / columns * height, index % columns * width);
+        let pixels = image[top..top + height]
+            .iter()
+            .map(|row| row[left..left + width].to_vec())
+            .collect();
+        glyphs.insert(c, Glyph { width, pixels });
+    }
+    Ok(BitmapFont {
+        name: String::new(),
+        height,
+        ascent: guess_ascent(&glyphs, height),
+        glyphs,
+    })
+}
+
+/// Reads a PSF or BDF font, whichever `data` is
+pub fn read_bitmap_font(data: &[u8]) -> Result<BitmapFont, FontError> {
+    if data.starts_with(PSF1_MAGIC) || data.starts_with(PSF2_MAGIC) {
+        read_psf(data)
+    } else {
+        let bdf = std::str::from_utf8(data).map_err(|_| syntax("not a PSF or BDF font"))?;
+        read_bdf(bdf)
+    }
+}
+
+pub fn read_bitmap_file<P: AsRef<Path>>(path: P) -> io::Result<BitmapFont> {
+    let disp = format!("{}", path.as_ref().display());
+    let name = path.as_ref().file_name().map(|name| name.to_string_lossy());
+    let name = name.map(String::from).unwrap_or_default();
+    let data = fs::read(&path)?;
+    let mut font = read_bitmap_font(&data)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", disp, e)))?;
+    // PSF fonts have no name of their own
+    if font.name.is_empty() {
+        font.name = name;
+    }
+    Ok(font)
+}
+
+/// An 8x4 glyph: a box with a dot in the middle
+#[cfg(test)]
+const BOX: [u8; 4] = [0xFF, 0x81, 0x99, 0xFF];
+
+#[test]
+fn read_psf1() {
+    let mut psf = vec![0x36, 0x04, 0x02, 4];
+    for _ in 0..256 {
+        psf.extend(BOX);
+    }
+    // glyph 0 is 'H' and U+2588, glyph 1 'A' then a sequence
+    psf.extend([0x48, 0x00, 0x88, 0x25, 0xFF, 0xFF]);
+    psf.extend([0x41, 0x00, 0xFE, 0xFF, 0x41, 0x00, 0x01, 0x03, 0xFF, 0xFF]);
+    for _ in 2..256 {
+        psf.extend([0xFF, 0xFF]);
+    }
+    let font = read_psf(&psf).unwrap();
+    assert_eq!((font.height, font.ascent), (4, 4));
+    let codes: Vec<_> = font.glyphs.keys().copied().collect();
+    assert_eq!(codes, vec!['A', 'H', '█']);
+    let glyph = &font.glyphs[&'H'];
+    assert_eq!(glyph.width, 8);
+    assert_eq!(glyph.pixels[2], [1, 0, 0, 1, 1, 0, 0, 1].map(|p| p == 1));
+}
+
+#[test]
+fn read_psf2() {
+    let mut psf = PSF2_MAGIC.to_vec();
+    // version, header size, flags, glyphs, glyph size, height, width
+    for field in [0, 32, 1, 2, 8, 4, 10] {
+        psf.extend(u32::to_le_bytes(field));
+    }
+    // two bytes a row, of which the last 6 bits are padding
+    psf.extend([0xFF, 0xC0, 0x80, 0x40, 0x80, 0x40, 0xFF, 0xC0]);
+    psf.extend([0; 8]);
+    psf.extend("é".as_bytes());
+    psf.push(0xFF);
+    psf.extend("ab".as_bytes());
+    psf.push(0xFF);
+    let font = read_psf(&psf).unwrap();
+    assert_eq!(font.height, 4);
+    assert_eq!(font.glyphs[&'é'].pixels[1].len(), 10);
+    assert!(font.glyphs[&'é'].pixels[1][9]);
+    assert!(font.glyphs[&'b'].pixels.iter().flatten().all(|p| !p));
+    assert_eq!(read_psf(&psf[..40]), Err(syntax("PSF: font cut short")));
+}
+
+#[test]
+fn reject_malformed() {
+    let bdf = |ascent: i32, bitmap: &str| {
+        read_bdf(&format!(
+            "STARTFONT 2.1\nFONT_ASCENT {}\nFONT_DESCENT 0\n\
+             STARTCHAR a\nENCODING 97\nBBX 8 1 0 0\nBITMAP\n{}\nENDCHAR\n",
+            ascent, bitmap
+        ))
+    };
+    assert_eq!(bdf(4, "é0"), Err(syntax("BDF: bitmap is not hex")));
+    assert_eq!(bdf(4, "+f"), Err(syntax("BDF: bitmap is not hex")));
+    assert_eq!(bdf(i32::MAX, "FF"), Err(syntax("font is too large")));
+    assert!(bdf(4, "FF").is_ok());
+
+    let psf2 = |size: u32, height: u32, width: u32| {
+        let mut psf = PSF2_MAGIC.to_vec();
+        for field in [0, 32, 0, 1, size, height, width] {
+            psf.extend(u32::to_le_bytes(field));
+        }
+        psf.extend([0xFF; 8]);
+        read_psf(&psf)
+    };
+    assert_eq!(
+        psf2(4, 4, 0),
+        Err(syntax("PSF: glyphs are no pixels across"))
+    );
+    assert_eq!(
+        psf2(2, 4, 8),
+        Err(syntax("PSF: glyphs are too small for their rows"))
+    );
+    assert_eq!(psf2(8, 4, 8).map(|font| font.height), Ok(4));
+
+    let empty = BitmapFont {
+        name: String::new(),
+        height: 0,
+        ascent: 0,
+        glyphs: BTreeMap::new(),
+    };
+    assert_eq!(empty.to_font(Pixels::default()), Err(FontError::ZeroHeight));
+}
+
+#[test]
+fn draw_pixels() {
+    let glyph = Glyph {
+        width: 4,
+        pixels: [[0, 1, 1, 0], [0, 1, 0, 0], [0, 0, 1, 0]]
+            .iter()
+            .map(|row| row.iter().map(|p| *p == 1).collect())
+            .collect(),
+    };
+    let draw = |pixels| -> Vec<String> {
+        let lines = draw(&glyph, pixels, '$');
+        lines.into_iter().map(String::from_iter).collect()
+    };
+    assert_eq!(draw(Pixels::default()), ["##$", "# $", " #$"]);
+    let pixels = Pixels::Chars {
+        dark: '@',
+        light: '.',
+    };
+    assert_eq!(draw(pixels), ["@@$", "@.$", ".@$"]);
+    assert_eq!(draw(Pixels::HalfBlocks), ["█▀$", " ▀$"]);
+
+    assert_eq!("half".parse(), Ok(Pixels::HalfBlocks));
+    assert_eq!(
+        "█".parse(),
+        Ok(Pixels::Chars {
+            dark: '█',
+            light: ' '
+        })
+    );
+    assert_eq!("@.".parse(), Ok(pixels));
+    assert!("###".parse::<Pixels>().is_err());
+}
@@ -0,0 +1,380 @@
+//! Borders drawn around rendered art, like those of `boxes` or TOIlet's
+//! border filter.
+//!
+//! A `Border` is drawn from tiles: one for each corner, one repeated
+//! along the top and bottom edges and one repeated down the sides. The
+//! tiles are single box drawing characters in one of the `Style`s, or
+//! glyphs of a FIGfont for borders as large as the art inside them.
+//! The art keeps its art lines and spans, moved in by the border, so it
+//! can still be hit tested.
+
+use crate::compose::{mend_wide, Padding, Position};
+use crate::font::Font;
+use crate::grid::Row;
+use crate::render::{Cell, Rendered};
+
+/// Which characters to draw a border with
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// `+`, `-` and `|`
+    Ascii,
+    /// `┌─┐`
+    #[default]
+    Light,
+    /// `┏━┓`
+    Heavy,
+    /// `╔═╗`
+    Double,
+    /// `╭─╮`
+    Rounded,
+}
+
+impl Style {
+    /// The top left, top right, bottom left and bottom right corners,
+    /// then the horizontal and vertical edges
+    fn chars(self) -> [char; 6] {
+        match self {
+            Style::Ascii => ['+', '+', '+', '+', '-', '|'],
+            Style::Light => ['┌', '┐', '└', '┘', '─', '│'],
+            Style::Heavy => ['┏', '┓', '┗', '┛', '━', '┃'],
+            Style::Double => ['╔', '╗', '╚', '╝', '═', '║'],
+            Style::Rounded => ['╭', '╮', '╰', '╯', '─', '│'],
+        }
+    }
+}
+
+/// A rectangle of sub-characters that a border is built from
+#[derive(Debug, Clone, PartialEq)]
+struct Tile {
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Tile {
+    fn of(ch: char) -> Self {
+        Tile {
+            rows: vec![vec![Cell::new(ch)]],
+        }
+    }
+
+    /// The glyph `font` draws for `ch`, without the blank rows and
+    /// columns around it, or `ch` itself if the glyph is blank
+    fn glyph(font: &Font, ch: char) -> Self {
+        let hardblank = font.hardblank();
+        let mut rows: Vec<Vec<Cell>> = font
+            .get_character(&ch)
+            .art
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&c| Cell::new(if c == hardblank { ' ' } else { c }))
+                    .collect()
+            })
+            .collect();
+        let blank = |cell: &Cell| cell.ch == ' ';
+        rows.retain(|row| !row.iter().all(blank));
+        if rows.is_empty() {
+            return Tile::of(ch);
+        }
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.resize(width, Cell::new(' '));
+        }
+        let leading = |row: &Vec<Cell>| row.iter().take_while(|c| blank(c)).count();
+        let trailing = |row: &Vec<Cell>| row.iter().rev().take_while(|c| blank(c)).count();
+        let left = rows.iter().map(leading).min().unwrap_or(0);
+        let right = rows.iter().map(trailing).min().unwrap_or(0);
+        for row in rows.iter_mut() {
+            row.truncate(width - right);
+            row.drain(..left);
+        }
+        Tile { rows }
+    }
+
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+/// A border to draw around art, with blank space inside it and perhaps
+/// a title across the top. See the module documentation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Border {
+    /// top left, top right, bottom left and bottom right
+    corners: [Tile; 4],
+    horizontal: Tile,
+    vertical: Tile,
+    padding: Padding,
+    title: Option<String>,
+    title_position: Position,
+}
+
+impl Border {
+    /// A border of box drawing characters in `style`
+    pub fn new(style: Style) -> Self {
+        let [top_left, top_right, bottom_left, bottom_right, horizontal, vertical] =
+            style.chars().map(Tile::of);
+        Border::from_tiles(
+            [top_left, top_right, bottom_left, bottom_right],
+            horizontal,
+            vertical,
+        )
+    }
+
+    /// A border drawn with `font`'s own glyphs: `+` at the corners, `-`
+    /// along the top and bottom and `|` down the sides
+    pub fn from_font(font: &Font) -> Self {
+        let corner = Tile::glyph(font, '+');
+        Border::from_tiles(
+            [corner.clone(), corner.clone(), corner.clone(), corner],
+            Tile::glyph(font, '-'),
+            Tile::glyph(font, '|'),
+        )
+    }
+
+    fn from_tiles(corners: [Tile; 4], horizontal: Tile, vertical: Tile) -> Self {
+        Border {
+            corners,
+            horizontal,
+            vertical,
+            padding: Padding::default(),
+            title: None,
+            title_position: Position::Start,
+        }
+    }
+
+    /// Blank space between the border and the art
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// A line of plain text to write into the top edge. The border
+    /// widens to fit it.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Where along the top edge the title goes
+    pub fn title_position(mut self, position: Position) -> Self {
+        self.title_position = position;
+        self
+    }
+
+    /// The widest art that fits inside the border in `width` columns,
+    /// leaving room for the sides and padding. A title wider than the
+    /// art can still widen it.
+    pub fn inner_width(&self, width: usize) -> usize {
+        let sides = self.widths();
+        width
+            .saturating_sub(sides.left + sides.right)
+            .saturating_sub(self.padding.left + self.padding.right)
+    }
+
+    /// How wide the left and right sides are, and how high the top and
+    /// bottom edges
+    fn widths(&self) -> Padding {
+        let [top_left, top_right, bottom_left, bottom_right] = &self.corners;
+        let (horizontal, vertical) = (&self.horizontal, &self.vertical);
+        Padding {
+            top: top_left
+                .height()
+                .max(top_right.height())
+                .max(horizontal.height()),
+            right: top_right
+                .width()
+                .max(bottom_right.width())
+                .max(vertical.width()),
+            bottom: bottom_left
+                .height()
+                .max(bottom_right.height())
+                .max(horizontal.height()),
+            left: top_left
+                .width()
+                .max(bottom_left.width())
+                .max(vertical.width()),
+        }
+    }
+
+    /// The title as it is written into the border, with a space either
+    /// side
+    fn title_row(&self) -> Option<Row> {
+        let title = self.title.as_deref()?;
+        Some(Row::from_display(
+            " ".chars().chain(title.chars()).chain(" ".chars()),
+        ))
+    }
+}
+
+/// Repeats `tile` over `rows` and `columns` of `cells`, starting at
+/// their first row and column
+fn tile(cells: &mut [Vec<Cell>], tile: &Tile, rows: (usize, usize), columns: (usize, usize)) {
+    if tile.width() == 0 {
+        return;
+    }
+    let tiles = tile.rows.iter().cycle();
+    for (row, tile) in cells[rows.0..rows.1].iter_mut().zip(tiles) {
+        for (cell, ch) in row[columns.0..columns.1]
+            .iter_mut()
+            .zip(tile.iter().cycle())
+        {
+            *cell = *ch;
+        }
+    }
+}
+
+impl Rendered {
+    /// This art inside `border`
+    pub fn framed(&self, border: &Border) -> Rendered {
+        let title = border.title_row();
+        let mut inner = self.pad(border.padding);
+        // room for the title, and an edge tile either side of it
+        let title_width = title.as_ref().map_or(0, |title| title.len() + 2);
+        if inner.width() < title_width {
+            inner = inner.aligned(title_width, Position::Start);
+        }
+        let sides = border.widths();
+        let mut framed = inner.pad(sides);
+        let (width, height) = (framed.width(), framed.height());
+        let (inner_width, inner_height) = (inner.width(), inner.height());
+        let cells = &mut framed.cells;
+
+        // each tile is centred across the edge it runs along
+        let Border {
+            corners,
+            horizontal,
+            vertical,
+            ..
+        } = border;
+        let across = |size: usize, room: usize| Position::Middle.offset(size, room);
+        let top = across(horizontal.height(), sides.top);
+        let bottom = height - sides.bottom + across(horizontal.height(), sides.bottom);
+        let (left, right) = (sides.left, width - sides.right);
+        tile(
+            cells,
+            horizontal,
+            (top, top + horizontal.height()),
+            (left, right),
+        );
+        tile(
+            cells,
+            horizontal,
+            (bottom, bottom + horizontal.height()),
+            (left, right),
+        );
+        let left = across(vertical.width(), sides.left);
+        let right = width - sides.right + across(vertical.width(), sides.right);
+        let (top, bottom) = (sides.top, height - sides.bottom);
+        tile(
+            cells,
+            vertical,
+            (top, bottom),
+            (left, left + vertical.width()),
+        );
+        tile(
+            cells,
+            vertical,
+            (top, bottom),
+            (right, right + vertical.width()),
+        );
+        for (corner, index) in corners.iter().zip(0..) {
+            let (rows, first_row) = match index / 2 {
+                0 => (sides.top, 0),
+                _ => (sides.bottom, sides.top + inner_height),
+            };
+            let (columns, first_column) = match index % 2 {
+                0 => (sides.left, 0),
+                _ => (sides.right, sides.left + inner_width),
+            };
+            let row = first_row + across(corner.height(), rows);
+            let column = first_column + across(corner.width(), columns);
+            tile(
+                cells,
+                corner,
+                (row, row + corner.height()),
+                (column, column + corner.width()),
+            );
+        }
+
+        if let Some(title) = title {
+            let row = across(1, sides.top);
+            let column =
+                sides.left + 1 + border.title_position.offset(title.len(), inner_width - 2);
+            for (&ch, cell) in title.iter().zip(&mut cells[row][column..]) {
+                *cell = Cell::new(ch);
+            }
+        }
+        for row in cells.iter_mut() {
+            mend_wide(row);
+        }
+        framed
+    }
+}
+
+#[test]
+fn frame_in_styles() {
+    let text = Rendered::from_rows(&["ab", "cd"], "ab");
+    let framed = text.framed(&Border::new(Style::Ascii));
+    assert_eq!(framed.display_rows(), ["+--+", "|ab|", "|cd|", "+--+"]);
+    assert_eq!(framed.hit_test(1, 1), [0]);
+    let framed = text.framed(&Border::new(Style::Rounded).padding(Padding::uniform(1)));
+    assert_eq!(
+        framed.display_rows(),
+        ["╭────╮", "│    │", "│ ab │", "│ cd │", "│    │", "╰────╯"]
+    );
+    assert_eq!(framed.locate(0), Some((0..6, 2..3)));
+    assert_eq!(
+        Rendered::from_rows(&[], "")
+            .framed(&Border::new(Style::Double))
+            .display_rows(),
+        ["╔╗", "╚╝"]
+    );
+}
+
+#[test]
+fn frame_with_title() {
+    let text = Rendered::from_rows(&["abcdefgh"], "abcdefgh");
+    let border = Border::new(Style::Light).title("hi");
+    assert_eq!(
+        text.framed(&border).display_rows(),
+        ["┌─ hi ───┐", "│abcdefgh│", "└────────┘"]
+    );
+    let border = border.title_position(Position::End);
+    assert_eq!(
+        text.framed(&border).display_rows(),
+        ["┌─── hi ─┐", "│abcdefgh│", "└────────┘"]
+    );
+    // the border widens to fit the title
+    let border = Border::new(Style::Heavy).title("漢字");
+    assert_eq!(
+        Rendered::from_rows(&["x"], "x")
+            .framed(&border)
+            .display_rows(),
+        ["┏━ 漢字 ━┓", "┃x       ┃", "┗━━━━━━━━┛"]
+    );
+}
+
+#[test]
+fn frame_from_tiles() {
+    let [corner, horizontal, vertical] = [&["o"][..], &["=", "-"], &["!!"]].map(|rows| Tile {
+        rows: rows
+            .iter()
+            .map(|row| row.chars().map(Cell::new).collect())
+            .collect(),
+    });
+    let border = Border::from_tiles(
+        [corner.clone(), corner.clone(), corner.clone(), corner],
+        horizontal,
+        vertical,
+    );
+    assert_eq!(
+        Rendered::from_rows(&["ab"], "ab")
+            .framed(&border)
+            .display_rows(),
+        ["o ==o ", "  --  ", "!!ab!!", "o ==o ", "  --  "]
+    );
+}
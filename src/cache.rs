@@ -0,0 +1,118 @@
+//! A compact binary cache of parsed fonts, so that large collections
+//! can be loaded without parsing every FIGfont again.
+//!
+//! A cache is the magic bytes, the length of the index as a 4 byte
+//! little-endian number, the index, then each font. The index and the
+//! fonts are MessagePack. Each font is decoded only when asked for, so
+//! opening a cache of hundreds of fonts to use one of them is cheap.
+
+use crate::font::{read_font_file, Font};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Error, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Starts every cache. The last byte is the format version, bumped
+/// whenever what gets serialized changes, so that a stale cache is
+/// rejected rather than misread.
+const MAGIC: &[u8; 8] = b"rustlet\x01";
+
+/// Parsed fonts by name
+pub type FontCollection = BTreeMap<String, Font>;
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> Error {
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+/// Reads every .flf and .tlf font in `dir`, naming each after its file
+pub fn read_font_dir<P: AsRef<Path>>(dir: P) -> io::Result<FontCollection> {
+    let mut fonts = FontCollection::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_font = path
+            .extension()
+            .is_some_and(|ext| ext == "flf" || ext == "tlf");
+        let Some(name) = path.file_stem().filter(|_| is_font) else {
+            continue;
+        };
+        let name = name.to_string_lossy().into_owned();
+        fonts.insert(name, read_font_file(&path)?);
+    }
+    Ok(fonts)
+}
+
+pub fn write_cache<W: Write>(fonts: &FontCollection, mut writer: W) -> io::Result<()> {
+    let mut index: Vec<(&str, usize)> = vec![];
+    let mut data = vec![];
+    for (name, font) in fonts {
+        rmp_serde::encode::write(&mut data, font).map_err(invalid)?;
+        index.push((name, data.len()));
+    }
+    let index = rmp_serde::to_vec(&index).map_err(invalid)?;
+    let index_len = u32::try_from(index.len()).map_err(invalid)?;
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&index_len.to_le_bytes())?;
+    writer.write_all(&index)?;
+    writer.write_all(&data)
+}
+
+/// An opened font cache
+pub struct FontCache {
+    data: Vec<u8>,
+    /// where each font is in `data`
+    index: BTreeMap<String, Range<usize>>,
+}
+
+impl FontCache {
+    /// Reads a cache written by `write_cache`, decoding only its index
+    pub fn read<R: Read>(mut reader: R) -> io::Result<FontCache> {
+        let mut cache = vec![];
+        reader.read_to_end(&mut cache)?;
+        let rest = cache
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("not a font cache, or one from another version"))?;
+        let (index_len, rest) = rest
+            .split_first_chunk::<4>()
+            .ok_or_else(|| invalid("font cache truncated"))?;
+        let index_len = u32::from_le_bytes(*index_len) as usize;
+        if rest.len() < index_len {
+            return Err(invalid("font cache truncated"));
+        }
+        let (index, data) = rest.split_at(index_len);
+        let ends: Vec<(String, usize)> = rmp_serde::from_slice(index).map_err(invalid)?;
+
+        let mut index = BTreeMap::new();
+        let mut start = 0;
+        for (name, end) in ends {
+            if end < start || end > data.len() {
+                return Err(invalid("font cache index out of range"));
+            }
+            index.insert(name, start..end);
+            start = end;
+        }
+        let data = data.to_vec();
+        Ok(FontCache { data, index })
+    }
+
+    /// Names of the fonts in the cache, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(String::as_str)
+    }
+
+    /// Decodes the font called `name`
+    pub fn get(&self, name: &str) -> io::Result<Font> {
+        let range = self.index.get(name).ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("{}: not in font cache", name))
+        })?;
+        rmp_serde::from_slice(&self.data[range.clone()]).map_err(invalid)
+    }
+
+    /// Decodes every font in the cache
+    pub fn fonts(&self) -> io::Result<FontCollection> {
+        self.names()
+            .map(|name| Ok((name.to_string(), self.get(name)?)))
+            .collect()
+    }
+}
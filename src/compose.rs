@@ -0,0 +1,331 @@
+//! Putting rendered art together: stacking pieces above or beside each
+//! other, laying one over another and padding them, for layouts such as
+//! a logo above a title or banners in columns.
+//!
+//! The result is `Rendered` art like any other, so it can be composed
+//! further. Its source is the sources of its pieces joined with
+//! newlines, and the spans of each piece are moved along with it, so
+//! hit testing still finds the characters each piece was drawn from.
+//! Stacking pieces vertically and padding keep their art lines; pieces
+//! put side by side or on top of each other become a single art line.
+
+use crate::grid::is_wide;
+use crate::render::{Cell, LineInfo, Rendered};
+use crate::text::Span;
+
+/// Where to put art in the room left beside it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// at the left, or at the top
+    #[default]
+    Start,
+    /// centred, with any odd column or row left after it
+    Middle,
+    /// at the right, or at the bottom
+    End,
+}
+
+impl Position {
+    /// How far into `room` to put something `size` long
+    pub(crate) fn offset(self, size: usize, room: usize) -> usize {
+        let slack = room.saturating_sub(size);
+        match self {
+            Position::Start => 0,
+            Position::Middle => slack / 2,
+            Position::End => slack,
+        }
+    }
+}
+
+/// Blank space to put around art, in columns and rows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Padding {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl Padding {
+    /// The same on every side
+    pub fn uniform(size: usize) -> Self {
+        Padding {
+            top: size,
+            right: size,
+            bottom: size,
+            left: size,
+        }
+    }
+}
+
+/// Moves a line `rows` rows down and its spans `columns` columns right,
+/// and their indices on by `index`
+fn moved(line: &LineInfo, rows: usize, columns: usize, index: usize) -> LineInfo {
+    LineInfo {
+        rows: line.rows.start + rows..line.rows.end + rows,
+        text: line.text.clone(),
+        spans: line
+            .spans
+            .iter()
+            .map(|span| Span {
+                index: span.index + index,
+                columns: span.columns.start + columns..span.columns.end + columns,
+            })
+            .collect(),
+    }
+}
+
+/// A blank grid that pieces of art are drawn onto
+struct Canvas {
+    cells: Vec<Vec<Cell>>,
+    lines: Vec<LineInfo>,
+    source: String,
+    /// how many characters `source` has
+    length: usize,
+    pieces: usize,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            cells: vec![vec![Cell::new(' '); width]; height],
+            lines: vec![],
+            source: String::new(),
+            length: 0,
+            pieces: 0,
+        }
+    }
+
+    /// Draws `art` with its top left corner at `row` and `column`. If
+    /// `transparent`, what is underneath shows through its spaces.
+    fn draw(&mut self, art: &Rendered, row: usize, column: usize, transparent: bool) {
+        for (cells, row) in art.rows().iter().zip(&mut self.cells[row..]) {
+            for (cell, target) in cells.iter().zip(&mut row[column..]) {
+                if !(transparent && cell.ch == ' ') {
+                    *target = *cell;
+                }
+            }
+        }
+    }
+
+    /// Adds the source of `art` to the canvas's, returning the index its
+    /// first character ends up at
+    fn add_source(&mut self, art: &Rendered) -> usize {
+        if self.pieces > 0 {
+            self.source.push('\n');
+            self.length += 1;
+        }
+        self.pieces += 1;
+        let index = self.length;
+        self.source.push_str(art.source());
+        self.length += art.source().chars().count();
+        index
+    }
+
+    /// Adds the source of `art`, and its lines moved to where it was
+    /// drawn
+    fn add_lines(&mut self, art: &Rendered, row: usize, column: usize) {
+        let index = self.add_source(art);
+        for line in &art.lines {
+            self.lines.push(moved(line, row, column, index));
+        }
+    }
+
+    /// Makes every line drawn a single line covering every row
+    fn merge_lines(&mut self) {
+        let spans = self.lines.drain(..).flat_map(|line| line.spans).collect();
+        self.lines = vec![LineInfo {
+            rows: 0..self.cells.len(),
+            text: self.source.clone(),
+            spans,
+        }];
+    }
+
+    fn finish(mut self) -> Rendered {
+        for row in self.cells.iter_mut() {
+            mend_wide(row);
+        }
+        Rendered {
+            cells: self.cells,
+            lines: self.lines,
+            source: self.source,
+        }
+    }
+}
+
+/// Blanks out the halves of double-width sub-characters that were drawn
+/// over
+pub(crate) fn mend_wide(row: &mut [Cell]) {
+    for i in 0..row.len() {
+        let continued = row.get(i + 1).is_some_and(Cell::is_continuation);
+        let orphaned = if row[i].is_continuation() {
+            i == 0 || !is_wide(row[i - 1].ch)
+        } else {
+            is_wide(row[i].ch) && !continued
+        };
+        if orphaned {
+            row[i] = Cell::new(' ');
+        }
+    }
+}
+
+/// Stacks `pieces` one above another, `gap` blank rows apart, placing
+/// each across the width of the widest as `position` says. Empty pieces
+/// are left out.
+pub fn vstack(pieces: &[&Rendered], gap: usize, position: Position) -> Rendered {
+    let pieces: Vec<_> = pieces.iter().filter(|piece| !piece.is_empty()).collect();
+    let width = pieces.iter().map(|piece| piece.width()).max().unwrap_or(0);
+    let gaps = gap * pieces.len().saturating_sub(1);
+    let height = pieces.iter().map(|piece| piece.height()).sum::<usize>() + gaps;
+    let mut canvas = Canvas::new(width, height);
+    let mut row = 0;
+    for piece in pieces {
+        if let Some(last) = canvas.lines.last_mut() {
+            // the gap belongs to the line above it
+            last.rows.end += gap;
+            row += gap;
+        }
+        let column = position.offset(piece.width(), width);
+        canvas.draw(piece, row, column, false);
+        canvas.add_lines(piece, row, column);
+        row += piece.height();
+    }
+    canvas.finish()
+}
+
+/// Puts `pieces` side by side, `gap` blank columns apart, placing each
+/// down the height of the tallest as `position` says. Empty pieces are
+/// left out.
+pub fn hstack(pieces: &[&Rendered], gap: usize, position: Position) -> Rendered {
+    let pieces: Vec<_> = pieces.iter().filter(|piece| !piece.is_empty()).collect();
+    let height = pieces.iter().map(|piece| piece.height()).max().unwrap_or(0);
+    let gaps = gap * pieces.len().saturating_sub(1);
+    let width = pieces.iter().map(|piece| piece.width()).sum::<usize>() + gaps;
+    let mut canvas = Canvas::new(width, height);
+    let mut column = 0;
+    for piece in pieces {
+        let row = position.offset(piece.height(), height);
+        canvas.draw(piece, row, column, false);
+        canvas.add_lines(piece, row, column);
+        column += piece.width() + gap;
+    }
+    canvas.merge_lines();
+    canvas.finish()
+}
+
+impl Rendered {
+    /// This art with `top` drawn over it, its top left corner at `row`
+    /// and `column`. This art shows through the spaces in `top`, and
+    /// grows to fit it.
+    pub fn overlay(&self, top: &Rendered, row: usize, column: usize) -> Rendered {
+        let width = self.width().max(column + top.width());
+        let height = self.height().max(row + top.height());
+        let mut canvas = Canvas::new(width, height);
+        canvas.draw(self, 0, 0, false);
+        canvas.add_lines(self, 0, 0);
+        canvas.draw(top, row, column, true);
+        canvas.add_lines(top, row, column);
+        canvas.merge_lines();
+        canvas.finish()
+    }
+
+    /// This art with blank space around it. The rows added above and
+    /// below belong to its first and last art lines.
+    pub fn pad(&self, padding: Padding) -> Rendered {
+        let width = padding.left + self.width() + padding.right;
+        let height = padding.top + self.height() + padding.bottom;
+        let mut canvas = Canvas::new(width, height);
+        canvas.draw(self, padding.top, padding.left, false);
+        canvas.add_lines(self, padding.top, padding.left);
+        match (canvas.lines.first_mut(), height) {
+            (None, 0) => (),
+            (None, _) => canvas.merge_lines(),
+            (Some(first), _) => first.rows.start = 0,
+        }
+        if let Some(last) = canvas.lines.last_mut() {
+            last.rows.end = height;
+        }
+        canvas.finish()
+    }
+
+    /// This art placed across `width` columns as `position` says, or as
+    /// it is if it is already as wide
+    pub fn aligned(&self, width: usize, position: Position) -> Rendered {
+        let left = position.offset(self.width(), width);
+        let right = width.saturating_sub(self.width() + left);
+        self.pad(Padding {
+            left,
+            right,
+            ..Padding::default()
+        })
+    }
+}
+
+#[test]
+fn stack_vertically() {
+    let logo = Rendered::from_rows(&["/\\", "\\/"], "<>");
+    let title = Rendered::from_rows(&["TITLE"], "title");
+    let stacked = vstack(&[&logo, &title], 1, Position::Middle);
+    assert_eq!(
+        stacked.display_rows(),
+        [" /\\  ", " \\/  ", "     ", "TITLE"]
+    );
+    assert_eq!(stacked.source(), "<>\ntitle");
+    assert_eq!(stacked.art_lines().count(), 2);
+    assert_eq!(stacked.hit_test(1, 2), [1]);
+    assert_eq!(stacked.hit_test(3, 0), [3]);
+    assert_eq!(stacked.locate(4), Some((3..4, 1..2)));
+}
+
+#[test]
+fn stack_horizontally() {
+    let left = Rendered::from_rows(&["ab", "cd", "ef"], "ab");
+    let right = Rendered::from_rows(&["x"], "x");
+    let stacked = hstack(&[&left, &right], 2, Position::End);
+    assert_eq!(stacked.display_rows(), ["ab   ", "cd   ", "ef  x"]);
+    assert_eq!(stacked.art_lines().count(), 1);
+    assert_eq!(stacked.hit_test(2, 4), [3]);
+    let stacked = hstack(
+        &[&left, &Rendered::from_rows(&[], ""), &right],
+        0,
+        Position::Start,
+    );
+    assert_eq!(stacked.display_rows(), ["abx", "cd ", "ef "]);
+}
+
+#[test]
+fn overlay_transparent() {
+    let base = Rendered::from_rows(&["-----", "-----"], "");
+    let top = Rendered::from_rows(&["a b", "c"], "ab");
+    assert_eq!(
+        base.overlay(&top, 1, 3).display_rows(),
+        ["----- ", "---a-b", "   c  "]
+    );
+    // a double-width sub-character half drawn over is blanked
+    let wide = Rendered::from_rows(&["漢字"], "");
+    assert_eq!(
+        wide.overlay(&Rendered::from_rows(&["x"], ""), 0, 1)
+            .display_rows(),
+        [" x字"]
+    );
+}
+
+#[test]
+fn pad_and_align() {
+    let text = Rendered::from_rows(&["ab"], "ab");
+    let padded = text.pad(Padding {
+        top: 1,
+        left: 2,
+        ..Padding::default()
+    });
+    assert_eq!(padded.display_rows(), ["    ", "  ab"]);
+    assert_eq!(padded.locate(1), Some((0..2, 3..4)));
+    assert_eq!(text.aligned(5, Position::End).display_rows(), ["   ab"]);
+    assert_eq!(text.aligned(1, Position::Middle).display_rows(), ["ab"]);
+    assert_eq!(
+        Rendered::from_texts(vec![], '$', "")
+            .pad(Padding::uniform(1))
+            .display_rows(),
+        ["  ", "  "]
+    );
+}
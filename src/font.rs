@@ -1,17 +1,22 @@
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while},
-    character::complete::{anychar, digit1, line_ending, space0},
-    combinator::{map, map_opt, map_res, opt, recognize},
+    bytes::complete::{tag, tag_no_case, take_while},
+    character::complete::{anychar, digit1, hex_digit1, line_ending, oct_digit1, space0},
+    combinator::{map, map_res, opt, recognize},
     multi::{many0, many_m_n},
-    sequence::{delimited, pair, terminated, tuple},
+    sequence::{delimited, pair, preceded, terminated, tuple},
     IResult,
 };
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
 
+use crate::grid::Row;
+#[cfg(test)]
+use crate::grid::CONTINUATION;
 use crate::settings::{Settings, SmushMode};
-use crate::text::Text;
+use crate::text::{Art, Text};
 
 use encoding_rs::UTF_8;
 use encoding_rs_io::DecodeReaderBytesBuilder;
@@ -20,19 +25,228 @@ use std::fs::File;
 use std::io::{self, Error, ErrorKind, Read};
 use std::path::Path;
 
+/// The characters every FIGfont must define, in the order they appear
+/// in the file: printable ASCII from space (32) to tilde (126), then
+/// the German characters Ä Ö Ü ä ö ü ß (Latin-1 196, 214, 220, 228,
+/// 246, 252 and 223). Fonts without German glyphs draw these as blanks
+/// or as their unaccented equivalents.
+pub const REQUIRED_CHARACTERS: [char; 102] = required_characters();
+
+/// The German characters at the end of `REQUIRED_CHARACTERS`
+pub const DEUTSCH_CHARACTERS: [char; 7] = ['Ä', 'Ö', 'Ü', 'ä', 'ö', 'ü', 'ß'];
+
+/// figlet's Deutsch mode (-D): the ASCII characters that the German
+/// variant of ISO 646 puts umlauts and eszett in place of, and the
+/// German character each becomes.
+pub const DEUTSCH_MAPPING: [(char, char); 7] = [
+    ('[', 'Ä'),
+    ('\\', 'Ö'),
+    (']', 'Ü'),
+    ('{', 'ä'),
+    ('|', 'ö'),
+    ('}', 'ü'),
+    ('~', 'ß'),
+];
+
+const fn required_characters() -> [char; 102] {
+    let mut table = [' '; 102];
+    let mut i = 0;
+    while i < 95 {
+        table[i] = (b' ' + i as u8) as char;
+        i += 1;
+    }
+    while i < 102 {
+        table[i] = DEUTSCH_CHARACTERS[i - 95];
+        i += 1;
+    }
+    table
+}
+
+/// Maps `ch` to its German character if Deutsch mode applies to it
+pub fn deutsch(ch: char) -> char {
+    DEUTSCH_MAPPING
+        .iter()
+        .find(|(ascii, _)| *ascii == ch)
+        .map_or(ch, |(_, german)| *german)
+}
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "FontParts"))]
 pub struct Font {
     pub settings: Settings,
     pub comment: String,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_ordered"))]
     characters: HashMap<char, Text>,
+    /// the comments on code-tagged characters, for those that have one
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_ordered"))]
+    comments: HashMap<char, String>,
+}
+
+/// Serializes a map by character in code point order, so that the same
+/// font always serializes the same way
+#[cfg(feature = "serde")]
+fn serialize_ordered<S: serde::Serializer, V: serde::Serialize>(
+    map: &HashMap<char, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    use serde::Serialize;
+    let ordered: std::collections::BTreeMap<_, _> = map.iter().collect();
+    ordered.serialize(serializer)
+}
+
+/// A font as it is serialized, checked as any other font is when it is
+/// read back. Fields it doesn't know are rejected rather than dropped,
+/// so that a font written by another version isn't half read.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FontParts {
+    settings: Settings,
+    comment: String,
+    characters: HashMap<char, Text>,
+    comments: HashMap<char, String>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<FontParts> for Font {
+    type Error = FontError;
+
+    fn try_from(parts: FontParts) -> Result<Font, FontError> {
+        Font::from_parts(
+            parts.settings,
+            parts.comment,
+            parts.characters,
+            parts.comments,
+        )
+    }
+}
+
+/// Why a font could not be read
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FontError {
+    /// the first line is not a valid flf2a or tlf2a header
+    Header,
+    /// the header gives a character height of zero
+    ZeroHeight,
+    /// there are fewer comment lines than the header says
+    Comment,
+    /// a required character is missing or has too few lines
+    Character(char),
+    /// a font in another format than FIGfont could not be understood
+    Syntax(String),
 }
 
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Header => write!(f, "invalid font header"),
+            FontError::ZeroHeight => write!(f, "character height is zero"),
+            FontError::Comment => write!(f, "font comment is cut short"),
+            FontError::Character(c) => {
+                write!(f, "character {:?} (code {}) is incomplete", c, *c as u32)
+            }
+            FontError::Syntax(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
 impl Font {
+    /// Builds a font from the art of each character, checking that
+    /// every required character is there and that each has as many rows
+    /// as the font is high
+    pub(crate) fn from_parts(
+        settings: Settings,
+        comment: String,
+        characters: HashMap<char, Text>,
+        comments: HashMap<char, String>,
+    ) -> Result<Font, FontError> {
+        if settings.charheight == 0 {
+            return Err(FontError::ZeroHeight);
+        }
+        let height = settings.charheight as usize;
+        if let Some(c) = REQUIRED_CHARACTERS
+            .iter()
+            .find(|c| !characters.contains_key(c))
+        {
+            return Err(FontError::Character(*c));
+        }
+        if let Some((c, _)) = characters.iter().find(|(_, text)| text.art.len() != height) {
+            return Err(FontError::Character(*c));
+        }
+        Ok(Font {
+            settings,
+            comment,
+            characters,
+            comments,
+        })
+    }
+
+    /// This font with `above` blank rows over every character and
+    /// `below` under it, its hardblank changed to `hardblank`, and its
+    /// baseline taken to be `baseline` rows down, so that fonts padded
+    /// to line up some other way than on their baselines are appended
+    /// as they are
+    pub(crate) fn padded(&self, above: u32, below: u32, baseline: u32, hardblank: char) -> Font {
+        let pad = |text: &Text| {
+            let mut text = text.pad_rows(above as usize, below as usize);
+            for row in text.art.iter_mut() {
+                for ch in row.iter_mut().filter(|ch| **ch == self.hardblank()) {
+                    *ch = hardblank;
+                }
+            }
+            text.baseline = baseline as usize;
+            text
+        };
+        let charheight = self.height() + above + below;
+        Font {
+            settings: Settings {
+                hardblank,
+                charheight,
+                baseline,
+                ..self.settings
+            },
+            comment: self.comment.clone(),
+            characters: self.characters.iter().map(|(c, t)| (*c, pad(t))).collect(),
+            comments: self.comments.clone(),
+        }
+    }
+
+    /// Every character the font draws, in code point order
+    pub fn characters(&self) -> impl Iterator<Item = (char, &Text)> {
+        let mut characters: Vec<_> = self.characters.iter().map(|(c, text)| (*c, text)).collect();
+        characters.sort_unstable_by_key(|(c, _)| *c);
+        characters.into_iter()
+    }
+
+    /// The comment on a code-tagged character's tag, if it has one
+    pub fn character_comment(&self, ch: char) -> Option<&str> {
+        self.comments.get(&ch).map(String::as_str)
+    }
+
+    /// The glyph for `ch`, or for '?' if the font lacks it. Every font
+    /// has a '?', as it is one of the required characters.
     pub fn get_character(&self, ch: &char) -> &Text {
         self.characters
             .get(ch)
-            .unwrap_or_else(|| self.characters.get(&'?').unwrap())
+            .unwrap_or_else(|| &self.characters[&'?'])
     }
+
+    /// Looks up the glyph for a grapheme cluster: its precomposed (NFC)
+    /// form if the font has it, otherwise its base character, so that
+    /// accented letters the font lacks are drawn without the accent.
+    pub fn get_grapheme(&self, grapheme: &str) -> &Text {
+        let mut composed = grapheme.nfc();
+        if let (Some(c), None) = (composed.next(), composed.next()) {
+            if let Some(text) = self.characters.get(&c) {
+                return text;
+            }
+        }
+        self.get_character(&grapheme.nfd().next().unwrap_or('?'))
+    }
+
     pub fn height(&self) -> u32 {
         self.settings.charheight
     }
@@ -104,9 +318,33 @@ fn non_line_ending(c: char) -> bool {
     c != '\n' && c != '\r'
 }
 
-fn parse_code_tag(input: &str) -> IResult<&str, (char, String)> {
+/// Reads a code tag's number, which may be decimal, octal with a
+/// leading 0, or hexadecimal with a leading 0x, and may be negative
+fn code_number(input: &str) -> IResult<&str, i64> {
+    let (input, negative) = preceded(space0, opt(tag("-")))(input)?;
+    let (input, number) = terminated(
+        alt((
+            map_res(preceded(tag_no_case("0x"), hex_digit1), |digits| {
+                i64::from_str_radix(digits, 16)
+            }),
+            map_res(preceded(tag("0"), oct_digit1), |digits| {
+                i64::from_str_radix(digits, 8)
+            }),
+            map_res(digit1, i64::from_str),
+        )),
+        space0,
+    )(input)?;
+    Ok((input, if negative.is_some() { -number } else { number }))
+}
+
+/// Parses a code tag line. Negative codes are for figlet's translation
+/// tables rather than input characters, so their glyphs are read but
+/// have no character.
+fn parse_code_tag(input: &str) -> IResult<&str, (Option<char>, String)> {
     tuple((
-        map_opt(delimited_u32, std::char::from_u32),
+        map(code_number, |code| {
+            u32::try_from(code).ok().and_then(std::char::from_u32)
+        }),
         map(line, String::from),
     ))(input)
 }
@@ -116,91 +354,103 @@ fn line(input: &str) -> IResult<&str, &str> {
     terminated(take_while(non_line_ending), line_ending)(input)
 }
 
-fn trim_line(line: &str) -> VecDeque<char> {
+/// Strips a line of a character's art of its endmarks. See `art_row`.
+fn trim_line(line: &str) -> Row {
     if line.len() < 2 {
-        VecDeque::new()
+        Row::new()
     } else {
-        let mut chars: VecDeque<_> = line.chars().collect();
-        let tailchar = chars.pop_back();
-        while chars.back() == tailchar.as_ref() {
-            chars.pop_back();
+        let mut chars: Vec<_> = line.chars().collect();
+        let tailchar = chars.pop();
+        while chars.last() == tailchar.as_ref() {
+            chars.pop();
         }
-        chars
+        art_row(chars)
+    }
+}
+
+/// Makes a row of art from its sub-characters, measuring it in columns.
+/// See `Row::from_display`.
+pub(crate) fn art_row(chars: Vec<char>) -> Row {
+    if chars.iter().all(char::is_ascii) {
+        return Row::from(chars);
     }
+    // Combining marks can't have a column of their own: compose what
+    // can be composed, and drop the rest
+    Row::from_display(chars.into_iter().nfc())
 }
 
-fn parse_character(input: &str, height: usize) -> IResult<&str, Vec<VecDeque<char>>> {
+fn parse_character(input: &str, height: usize) -> IResult<&str, Art> {
     map(many_m_n(height, height, line), |ch| {
         ch.into_iter().map(trim_line).collect::<Vec<_>>()
     })(input)
 }
 
-fn parse_font(input: &str) -> IResult<&str, Font> {
-    let (input, settings) = terminated(settingsline, line_ending)(input)?;
+fn parse_font(input: &str) -> Result<Font, FontError> {
+    let (input, settings) =
+        terminated(settingsline, line_ending)(input).map_err(|_| FontError::Header)?;
+    if settings.charheight == 0 {
+        return Err(FontError::ZeroHeight);
+    }
     let comlines = settings.commentlines as usize;
     let height = settings.charheight as usize;
-    let (input, comment) =
-        map(many_m_n(comlines, comlines, line), |lines| lines.join("\n"))(input)?;
+    let (input, comment) = map(many_m_n(comlines, comlines, line), |lines| lines.join("\n"))(input)
+        .map_err(|_| FontError::Comment)?;
     let parse_additional_character =
         tuple((parse_code_tag, |input| parse_character(input, height)));
 
     let (input, required_characters) =
-        many_m_n(102, 102, |input| parse_character(input, height))(input)?;
+        many_m_n(0, 102, |input| parse_character(input, height))(input)
+            .map_err(|_| FontError::Character(' '))?;
+    if let Some(&c) = REQUIRED_CHARACTERS.get(required_characters.len()) {
+        return Err(FontError::Character(c));
+    }
 
     let mut characters = HashMap::new();
+    let mut comments = HashMap::new();
 
-    // standard ascii
-    for (i, c) in (32u8..127).map(|i| i as char).enumerate() {
+    // standard ascii, then the German characters
+    for (c, art) in REQUIRED_CHARACTERS.iter().zip(required_characters) {
         let character = Text {
             text: c.to_string(),
-            art: required_characters[i].clone(),
-        };
-        characters.insert(c, character);
-    }
-
-    // additional required (German) characters
-    for (i, c) in [196u8, 214, 22, 228, 246, 252, 223]
-        .iter()
-        .map(|i| *i as char)
-        .enumerate()
-    {
-        let character = Text {
-            text: c.to_string(),
-            art: required_characters[i].clone(),
+            art,
+            spans: vec![],
+            baseline: settings.baseline as usize,
         };
-        characters.insert(c, character);
+        characters.insert(*c, character);
     }
 
     //println!("CHARACTERS: {:?}", characters);
     // what's left is additional characters
-    let (input, additional_characters) = many0(parse_additional_character)(input)?;
+    let additional_characters = many0(parse_additional_character)(input)
+        .map(|(_, characters)| characters)
+        .unwrap_or_default();
 
     //println!("Additional characters: {:#?}", additional_characters);
 
-    for ((c, _comment), art) in additional_characters {
+    for ((c, comment), art) in additional_characters {
+        let Some(c) = c else {
+            continue;
+        };
+        let comment = comment.trim();
+        if comment.is_empty() {
+            comments.remove(&c);
+        } else {
+            comments.insert(c, comment.to_string());
+        }
         let character = Text {
             text: c.to_string(),
             art,
+            spans: vec![],
+            baseline: settings.baseline as usize,
         };
         characters.insert(c, character);
     }
 
-    Ok((
-        input,
-        Font {
-            settings,
-            comment,
-            characters,
-        },
-    ))
+    Font::from_parts(settings, comment, characters, comments)
 }
 
-pub fn read_font(font_data: &str) -> Option<Font> {
-    if let Ok((_, font)) = parse_font(font_data) {
-        Some(font)
-    } else {
-        None
-    }
+pub fn read_font(font_data: &str) -> Result<Font, FontError> {
+    parse_font(font_data)
 }
 
 pub fn read_font_file<P: AsRef<Path>>(path: P) -> io::Result<Font> {
@@ -211,8 +461,93 @@ pub fn read_font_file<P: AsRef<Path>>(path: P) -> io::Result<Font> {
         .build(file);
     let mut out = String::new();
     transcoded.read_to_string(&mut out)?;
-    read_font(&out)
-        .ok_or_else(|| Error::new(ErrorKind::Other, format!("Problem with path: {}", disp)))
+    read_font(&out).map_err(|e| Error::new(ErrorKind::InvalidData, format!("{}: {}", disp, e)))
+}
+
+/// Writes `font` as a FIGfont, which `read_font` reads back as the same
+/// font. Characters other than the required ones, and any with a
+/// comment, get code tags. The header is tlf2a if the art has anything
+/// beyond ASCII, flf2a otherwise.
+pub fn write_font(font: &Font) -> String {
+    let settings = &font.settings;
+    let tagged: Vec<_> = font
+        .characters()
+        .filter(|(c, _)| !REQUIRED_CHARACTERS.contains(c) || font.comments.contains_key(c))
+        .collect();
+    let is_ascii = font
+        .characters
+        .values()
+        .flat_map(|text| text.art.iter())
+        .all(|row| row.iter().all(char::is_ascii));
+    let widest = font
+        .characters
+        .values()
+        .flat_map(|text| text.art.iter())
+        .map(|row| row.display().count() as u32 + 2)
+        .max()
+        .unwrap_or(0);
+    let comment_lines = comment_lines(&font.comment);
+
+    let mut out = format!(
+        "{}{} {} {} {} {} {} {} {} {}\n",
+        if is_ascii { "flf2a" } else { "tlf2a" },
+        settings.hardblank,
+        settings.charheight,
+        settings.baseline,
+        settings.maxlength.max(widest),
+        settings.smushmode.to_old_layout(),
+        comment_lines.len(),
+        settings.right2left as u32,
+        settings.smushmode.bits(),
+        tagged.len(),
+    );
+    for line in comment_lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for c in REQUIRED_CHARACTERS {
+        write_art(&mut out, &font.characters[&c]);
+    }
+    for (c, text) in tagged {
+        match font.comments.get(&c) {
+            Some(comment) => out.push_str(&format!("{}  {}\n", c as u32, comment)),
+            None => out.push_str(&format!("{}\n", c as u32)),
+        }
+        write_art(&mut out, text);
+    }
+    out
+}
+
+/// The lines of a font's comment, none if it is empty
+pub(crate) fn comment_lines(comment: &str) -> Vec<&str> {
+    if comment.is_empty() {
+        vec![]
+    } else {
+        comment.split('\n').collect()
+    }
+}
+
+/// Writes a character's rows, each ending with an endmark that can't be
+/// mistaken for part of the art, doubled on the last row
+fn write_art(out: &mut String, text: &Text) {
+    let height = text.art.len();
+    for (i, row) in text.art.iter().enumerate() {
+        let endmark = if row.display().last() == Some('@') {
+            '#'
+        } else {
+            '@'
+        };
+        out.extend(row.display());
+        out.push(endmark);
+        if i + 1 == height {
+            out.push(endmark);
+        }
+        out.push('\n');
+    }
+}
+
+pub fn write_font_file<P: AsRef<Path>>(font: &Font, path: P) -> io::Result<()> {
+    std::fs::write(path, write_font(font))
 }
 
 #[test]
@@ -220,7 +555,7 @@ fn parse_font_example() {
     let fontstr = include_str!("../fonts/small.flf");
     let res = parse_font(fontstr);
     assert!(res.is_ok());
-    if let Ok((_, font)) = res {
+    if let Ok(font) = res {
         assert!(font.comment.contains("Small by Glenn Chappell"));
         let lowercase_a: Vec<Vec<_>> = [r"       ", r"  __ _ ", r" / _` |", r" \__,_|", r"       "]
             .iter()
@@ -241,6 +576,105 @@ fn parse_font_example() {
     }
 }
 
+#[test]
+fn parse_font_german() {
+    let font = read_font(include_str!("../fonts/standard.flf")).unwrap();
+    let row = |c: char, i: usize| -> String { font.get_character(&c).art[i].iter().collect() };
+    assert_eq!(row('Ä', 2), "   /_\\  ");
+    assert_eq!(row('Ö', 2), "  / _ \\ ");
+    assert_eq!(row('Ü', 1), " (_) (_)");
+    assert_eq!(row('ä', 4), "  \\__,_|");
+    assert_eq!(row('ü', 4), "  \\__,_|");
+    assert_eq!(row('ß', 2), " | |/ /");
+    assert_ne!(font.get_character(&'ö'), font.get_character(&'!'));
+    assert!(!font.characters.contains_key(&'\u{16}'));
+}
+
+#[test]
+fn get_grapheme_fallbacks() {
+    let standard = read_font(include_str!("../fonts/standard.flf")).unwrap();
+    let e_acute = standard.get_character(&'é');
+    assert_ne!(e_acute, standard.get_character(&'e'));
+    assert_eq!(standard.get_grapheme("é"), e_acute);
+    assert_eq!(standard.get_grapheme("e\u{301}"), e_acute);
+    // no precomposed form: the base character
+    assert_eq!(
+        standard.get_grapheme("q\u{301}"),
+        standard.get_character(&'q')
+    );
+
+    let banner = read_font(include_str!("../fonts/banner.flf")).unwrap();
+    assert!(!banner.characters.contains_key(&'é'));
+    assert_eq!(banner.get_grapheme("é"), banner.get_character(&'e'));
+    assert_eq!(banner.get_grapheme("e\u{301}"), banner.get_character(&'e'));
+}
+
+#[test]
+fn parse_font_wide() {
+    let font = read_font(include_str!("../tests/data/fonts/wide.tlf")).unwrap();
+    let rows = |c: char| -> Vec<Row> { font.get_character(&c).art.clone() };
+    assert_eq!(rows('A'), vec![vec!['Ａ', CONTINUATION], vec!['_', '_']]);
+    assert_eq!(
+        rows('漢')[0],
+        vec!['漢', CONTINUATION, '字', CONTINUATION, ' ']
+    );
+    assert_eq!(rows('漢')[1].len(), 5);
+    // combining marks are composed, zero-width joiners dropped
+    assert_eq!(rows('é')[0], vec!['é', 'é']);
+    assert_eq!(rows('❤')[0], vec!['<', '3']);
+}
+
+#[test]
+fn parse_code_tags() {
+    assert_eq!(
+        code_number("160  NO-BREAK SPACE"),
+        Ok(("NO-BREAK SPACE", 160))
+    );
+    assert_eq!(code_number("0x05D0 HEBREW"), Ok(("HEBREW", 0x05D0)));
+    assert_eq!(code_number("0X5d0"), Ok(("", 0x05D0)));
+    assert_eq!(code_number("0177"), Ok(("", 127)));
+    assert_eq!(code_number("0"), Ok(("", 0)));
+    assert_eq!(code_number("-2"), Ok(("", -2)));
+
+    let font = read_font(include_str!("../fonts/ivrit.flf")).unwrap();
+    assert!(font.characters.contains_key(&'\u{05D0}'));
+    assert!(font.characters.contains_key(&'\u{05EA}'));
+}
+
+#[test]
+fn parse_character_comments() {
+    let font = read_font(include_str!("../fonts/ivrit.flf")).unwrap();
+    assert_eq!(
+        font.character_comment('\u{05D0}'),
+        Some("HEBREW LETTER ALEF")
+    );
+    assert_eq!(font.character_comment('A'), None);
+}
+
+#[test]
+fn write_font_round_trip() {
+    for fontstr in [
+        include_str!("../fonts/standard.flf"),
+        include_str!("../fonts/ivrit.flf"),
+        include_str!("../tests/data/fonts/wide.tlf"),
+    ] {
+        let font = read_font(fontstr).unwrap();
+        let written = write_font(&font);
+        assert_eq!(read_font(&written), Ok(font));
+    }
+}
+
+#[test]
+fn write_font_endmarks() {
+    let mut font = read_font(include_str!("../fonts/standard.flf")).unwrap();
+    let at = font.characters.get_mut(&'@').unwrap();
+    at.art[0] = Row::from(vec![' ', '@']);
+    let written = write_font(&font);
+    assert!(written.starts_with("flf2a$ 6 5 16 15 "));
+    assert!(written.contains("\n @#\n"));
+    assert_eq!(read_font(&written), Ok(font));
+}
+
 #[test]
 fn parse_settings_toilet() {
     let settings = "tlf2a 3 3 8 -1 22 0 \r\n";
@@ -346,3 +780,28 @@ fn parse_settings_block() {
         ))
     );
 }
+
+#[test]
+fn parse_font_malformed() {
+    assert_eq!(read_font(""), Err(FontError::Header));
+    assert_eq!(read_font("flf2a$ 0 0 8 -1 0\n"), Err(FontError::ZeroHeight));
+    assert_eq!(
+        read_font("flf2a$ 1 1 8 -1 2\nonly one\n"),
+        Err(FontError::Comment)
+    );
+    assert_eq!(
+        read_font("flf2a$ 2 2 8 -1 0\n @\n @@\n!@\n"),
+        Err(FontError::Character('!'))
+    );
+}
+
+#[test]
+fn get_character_falls_back_to_question_mark() {
+    let mut fontstr = String::from("flf2a$ 1 1 8 -1 0\n");
+    for c in REQUIRED_CHARACTERS {
+        fontstr.push_str(&format!("{}@@\n", c));
+    }
+    let font = read_font(&fontstr).expect("font parses");
+    assert_eq!(font.get_character(&'\u{2603}'), font.get_character(&'?'));
+    assert_eq!(font.get_grapheme(""), font.get_character(&'?'));
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use unicode_width::UnicodeWidthChar;
+
+/// Fills the column after a double-width sub-character, so that each
+/// sub-character in a `Row` takes up exactly one column
+pub const CONTINUATION: char = '\u{FFFF}';
+
+/// Whether `ch` is a double-width sub-character or the continuation
+/// of one. These are drawn whole or not at all, never smushed.
+pub fn is_wide(ch: char) -> bool {
+    ch == CONTINUATION || ch.width() == Some(2)
+}
+
+/// One row of artwork. A contiguous buffer of sub-characters with
+/// spare room kept in front of the content, so that text laid out
+/// right-to-left can grow at the front as cheaply as left-to-right
+/// text grows at the back, and trimming the front is just an offset
+/// change.
+#[derive(Clone, Default)]
+pub struct Row {
+    buf: Vec<char>,
+    /// index in `buf` of the first sub-character
+    start: usize,
+}
+
+impl Row {
+    pub fn new() -> Self {
+        Row::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Row {
+            buf: Vec::with_capacity(capacity),
+            start: 0,
+        }
+    }
+
+    /// Builds a row from sub-characters as they appear on screen,
+    /// following each double-width one with a `CONTINUATION` and
+    /// dropping zero-width ones
+    pub fn from_display<I: IntoIterator<Item = char>>(chars: I) -> Self {
+        let mut row = Row::new();
+        for ch in chars {
+            match ch.width() {
+                Some(0) => (),
+                Some(2) => {
+                    row.push(ch);
+                    row.push(CONTINUATION);
+                }
+                _ => row.push(ch),
+            }
+        }
+        row
+    }
+
+    /// The sub-characters to print, without continuations
+    pub fn display(&self) -> impl Iterator<Item = char> + '_ {
+        self.iter().copied().filter(|&ch| ch != CONTINUATION)
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.buf.push(ch);
+    }
+
+    /// Inserts `chars` before the first sub-character
+    pub fn prepend(&mut self, chars: &[char]) {
+        if self.start < chars.len() {
+            let len = self.len();
+            let room = chars.len().max(len);
+            let mut buf = Vec::with_capacity(room + len);
+            buf.resize(room, ' ');
+            buf.extend_from_slice(&self.buf[self.start..]);
+            self.buf = buf;
+            self.start = room;
+        }
+        self.start -= chars.len();
+        self.buf[self.start..self.start + chars.len()].copy_from_slice(chars);
+    }
+
+    /// Removes the first `n` sub-characters
+    pub fn remove_front(&mut self, n: usize) {
+        self.start += n.min(self.len());
+    }
+
+    /// Removes the first `n` sub-characters, copying them into `into`
+    pub fn take_front(&mut self, n: usize, into: &mut Vec<char>) {
+        let n = n.min(self.len());
+        into.extend_from_slice(&self[..n]);
+        self.start += n;
+    }
+}
+
+/// Serializes as a string of its sub-characters
+#[cfg(feature = "serde")]
+impl serde::Serialize for Row {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.iter().collect::<String>())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Row {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl serde::de::Visitor<'_> for RowVisitor {
+            type Value = Row;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a row of sub-characters")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, row: &str) -> Result<Row, E> {
+                let mut buf = Vec::with_capacity(row.len());
+                buf.extend(row.chars());
+                Ok(Row::from(buf))
+            }
+        }
+
+        deserializer.deserialize_str(RowVisitor)
+    }
+}
+
+impl Deref for Row {
+    type Target = [char];
+
+    fn deref(&self) -> &[char] {
+        &self.buf[self.start..]
+    }
+}
+
+impl DerefMut for Row {
+    fn deref_mut(&mut self) -> &mut [char] {
+        &mut self.buf[self.start..]
+    }
+}
+
+impl Extend<char> for Row {
+    fn extend<T: IntoIterator<Item = char>>(&mut self, iter: T) {
+        self.buf.extend(iter)
+    }
+}
+
+impl<'a> Extend<&'a char> for Row {
+    fn extend<T: IntoIterator<Item = &'a char>>(&mut self, iter: T) {
+        self.buf.extend(iter)
+    }
+}
+
+impl FromIterator<char> for Row {
+    fn from_iter<T: IntoIterator<Item = char>>(iter: T) -> Self {
+        Row {
+            buf: iter.into_iter().collect(),
+            start: 0,
+        }
+    }
+}
+
+impl From<Vec<char>> for Row {
+    fn from(buf: Vec<char>) -> Self {
+        Row { buf, start: 0 }
+    }
+}
+
+impl PartialEq for Row {
+    fn eq(&self, other: &Row) -> bool {
+        **self == **other
+    }
+}
+
+impl PartialEq<Vec<char>> for Row {
+    fn eq(&self, other: &Vec<char>) -> bool {
+        **self == **other
+    }
+}
+
+impl fmt::Debug for Row {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
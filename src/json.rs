@@ -0,0 +1,142 @@
+//! FIGfonts as JSON, so that fonts can be edited with generic tools.
+//!
+//! A font is an object with the FIGfont header's fields, its comment,
+//! and its characters keyed by decimal code point. Each character is
+//! its rows of art without endmarks, hardblanks included, and the
+//! comment from its code tag if it has one:
+//!
+//! ```json
+//! {
+//!   "header": {
+//!     "hardblank": "$",
+//!     "height": 1,
+//!     "baseline": 1,
+//!     "max_length": 3,
+//!     "old_layout": 0,
+//!     "full_layout": 64,
+//!     "print_direction": 0
+//!   },
+//!   "comment": "",
+//!   "characters": {
+//!     "32": { "rows": ["$"] },
+//!     "228": { "rows": ["a"], "comment": "LATIN SMALL LETTER A WITH DIAERESIS" }
+//!   }
+//! }
+//! ```
+//!
+//! The comment line and code tag counts are left out, since they follow
+//! from the rest. `SCHEMA` is a JSON Schema for the format.
+
+use crate::font::{art_row, comment_lines, Font, FontError};
+use crate::settings::{Settings, SmushMode};
+use crate::text::Text;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A JSON Schema for fonts written by `write_font`
+pub const SCHEMA: &str = include_str!("../schema/figfont.schema.json");
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Header {
+    hardblank: char,
+    height: u32,
+    baseline: u32,
+    max_length: u32,
+    old_layout: i32,
+    /// old_layout says how to lay out the font when this is left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    full_layout: Option<u32>,
+    /// 0 for left-to-right, 1 for right-to-left
+    #[serde(default)]
+    print_direction: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Character {
+    rows: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonFont {
+    header: Header,
+    #[serde(default)]
+    comment: String,
+    characters: BTreeMap<u32, Character>,
+}
+
+/// Reads a font written by `write_font`
+pub fn read_font(json: &str) -> Result<Font, FontError> {
+    let font: JsonFont =
+        serde_json::from_str(json).map_err(|e| FontError::Syntax(e.to_string()))?;
+    let header = font.header;
+    let smushmode = match header.full_layout {
+        Some(bits) => SmushMode::from(bits),
+        None => SmushMode::from_old_layout(header.old_layout),
+    };
+    let commentlines = comment_lines(&font.comment).len() as u32;
+    let settings = Settings {
+        hardblank: header.hardblank,
+        charheight: header.height,
+        baseline: header.baseline,
+        maxlength: header.max_length,
+        commentlines,
+        right2left: header.print_direction != 0,
+        smushmode,
+    };
+
+    let mut characters = HashMap::new();
+    let mut comments = HashMap::new();
+    for (code, character) in font.characters {
+        let c = char::from_u32(code)
+            .ok_or_else(|| FontError::Syntax(format!("{} is not a character code", code)))?;
+        let art = character
+            .rows
+            .iter()
+            .map(|row| art_row(row.chars().collect()))
+            .collect();
+        let text = Text {
+            text: c.to_string(),
+            art,
+            spans: vec![],
+            baseline: settings.baseline as usize,
+        };
+        characters.insert(c, text);
+        if let Some(comment) = character.comment {
+            comments.insert(c, comment);
+        }
+    }
+    Font::from_parts(settings, font.comment, characters, comments)
+}
+
+/// Writes `font` as pretty-printed JSON
+pub fn write_font(font: &Font) -> String {
+    let settings = &font.settings;
+    let header = Header {
+        hardblank: settings.hardblank,
+        height: settings.charheight,
+        baseline: settings.baseline,
+        max_length: settings.maxlength,
+        old_layout: settings.smushmode.to_old_layout(),
+        full_layout: Some(settings.smushmode.bits()),
+        print_direction: settings.right2left as u32,
+    };
+    let characters = font
+        .characters()
+        .map(|(c, text)| {
+            let rows = text.art.iter().map(|row| row.display().collect()).collect();
+            let comment = font.character_comment(c).map(String::from);
+            (c as u32, Character { rows, comment })
+        })
+        .collect();
+    let font = JsonFont {
+        header,
+        comment: font.comment.clone(),
+        characters,
+    };
+    serde_json::to_string_pretty(&font).expect("fonts are always valid JSON")
+}
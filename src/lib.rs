@@ -2,9 +2,30 @@
 extern crate bitflags;
 extern crate nom;
 
+mod bidi;
+pub mod bitmap;
+pub mod border;
+#[cfg(feature = "serde")]
+pub mod cache;
+pub mod compose;
 pub mod font;
+pub mod grid;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod mix;
+mod netpbm;
+pub mod pixelate;
+pub mod render;
+pub mod scale;
 pub mod settings;
+pub mod stream;
 pub mod text;
+pub mod wrap;
 
-pub use font::read_font;
-pub use text::art_lines;
+pub use font::{read_font, FontError};
+pub use pixelate::Pixelate;
+pub use render::Rendered;
+pub use scale::Scale;
+pub use stream::{art_stream, Overflow};
+pub use text::{art_lines, render, Align, Span};
+pub use wrap::Wrap;
@@ -1,36 +1,337 @@
-#[macro_use]
-extern crate bitflags;
-extern crate nom;
-
-mod font;
-mod settings;
-mod text;
-
-use font::{read_font, read_font_file, Font};
-//use settings::Settings;
-use text::art_lines;
+use rustlet::bitmap::{read_bitmap_file, read_glyph_sheet, Pixels};
+use rustlet::border::{Border, Style};
+use rustlet::compose::{vstack, Padding, Position};
+use rustlet::font::{read_font, read_font_file, write_font, Font};
+#[cfg(feature = "json")]
+use rustlet::json;
+use rustlet::mix::{parse_markup, Mix, Rules};
+use rustlet::settings::PrintDirection;
+use rustlet::stream::ArtStream;
+use rustlet::text::ArtOutput;
+use rustlet::{art_stream, Overflow, Pixelate, Rendered, Scale, Wrap};
 
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead};
+use std::iter;
 use std::path::{Path, PathBuf};
+use std::process;
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
-    /// Message to convert to ascii art
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Message to convert to ascii art. Read from standard input if
+    /// omitted.
     message: Vec<String>,
 
     /// Set a custom font directory
     #[arg(short = 'd', long = "font-directory", value_name = "DIR")]
     fontdir: Option<PathBuf>,
 
-    /// Set max-width to value
+    /// Set max-width to value, or 0 to never wrap
     #[arg(short, long, default_value_t = 80)]
     width: u16,
 
+    /// Never wrap, however long the lines get. Same as --width 0
+    #[arg(long)]
+    no_wrap: bool,
+
     /// Set the font
     #[arg(short = 'f', long = "font", value_name = "FONT")]
     font: Option<String>,
+
+    /// Deutsch mode: draw [\]{|}~ as the German characters ÄÖÜäöüß
+    #[arg(short = 'D', long, overrides_with = "english")]
+    deutsch: bool,
+
+    /// Turn off Deutsch mode (the default)
+    #[arg(short = 'E', long, overrides_with = "deutsch")]
+    english: bool,
+
+    /// Print left-to-right, whatever the font says
+    #[arg(short = 'L', long, overrides_with_all = ["right_to_left", "font_direction"])]
+    left_to_right: bool,
+
+    /// Print right-to-left, whatever the font says
+    #[arg(short = 'R', long, overrides_with_all = ["left_to_right", "font_direction"])]
+    right_to_left: bool,
+
+    /// Print in the direction given by the font (the default)
+    #[arg(short = 'X', long, overrides_with_all = ["left_to_right", "right_to_left"])]
+    font_direction: bool,
+
+    /// What to do with a word too wide for a line
+    #[arg(long, value_enum, default_value_t = OverflowMode::Break)]
+    overflow: OverflowMode,
+
+    /// How to choose where lines break
+    #[arg(long, value_enum, default_value_t = WrapMode::Greedy)]
+    wrap: WrapMode,
+
+    /// Reorder mixed right-to-left and left-to-right text with the
+    /// Unicode Bidirectional Algorithm, laying out each line in its
+    /// own base direction
+    #[arg(long)]
+    bidi: bool,
+
+    /// Redraw the art in terminal graphics, packing several
+    /// sub-characters into each column, to fit large fonts in narrow
+    /// terminals
+    #[arg(long, value_enum, value_name = "MODE")]
+    pixelate: Option<PixelateMode>,
+
+    /// Draw the art bigger or smaller, across and down, such as 2x1 for
+    /// twice as wide or 1/2x1/2 for half the size. Each part is at most 64.
+    #[arg(long, value_name = "AxD")]
+    scale: Option<Scale>,
+
+    /// Switch fonts within the message with {font=NAME} tags, lining
+    /// the fonts up on their baselines. {font} switches back to the
+    /// font given by --font, and {{ stands for {
+    #[arg(long)]
+    markup: bool,
+
+    /// Whose layout rules to use when fonts are switched with --markup
+    #[arg(long, value_enum, default_value_t = MixRules::Loosest)]
+    mix_rules: MixRules,
+
+    /// Draw a border around the art, within --width. Input read from
+    /// standard input goes inside one border once it has all been read
+    #[arg(long, value_enum, value_name = "STYLE")]
+    border: Option<BorderStyle>,
+
+    /// Blank columns and rows between the border and the art
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "border")]
+    border_padding: usize,
+
+    /// A title to write into the top of the border
+    #[arg(long, requires = "border")]
+    title: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert a font between FIGfont (.flf or .tlf) and JSON (.json),
+    /// or from a BDF (.bdf) or PSF (.psf) bitmap font, going by the file
+    /// extensions
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+
+        /// How to draw a bitmap font's pixels: a character for inked
+        /// pixels, optionally followed by one for the rest, or "half" for
+        /// half blocks at half the height
+        #[arg(long, default_value = "#")]
+        pixels: Pixels,
+    },
+    /// Make a font from glyphs drawn in a PBM or PGM image, divided into
+    /// a grid of equal cells that hold the characters in order, left to
+    /// right and then top to bottom
+    FontFromImage {
+        image: PathBuf,
+
+        /// The size of each cell, in pixels
+        #[arg(long, value_name = "WxH", value_parser = parse_grid)]
+        grid: (usize, usize),
+
+        /// The characters drawn in the cells
+        #[arg(long)]
+        chars: String,
+
+        /// The sub-character to draw dark pixels with
+        #[arg(long, default_value_t = '#')]
+        dark: char,
+
+        /// The sub-character to draw light pixels with
+        #[arg(long, default_value_t = ' ')]
+        light: char,
+
+        /// Where to save the font, as a FIGfont (.flf or .tlf) or JSON
+        /// (.json). Printed as a FIGfont if omitted.
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+fn parse_grid(grid: &str) -> Result<(usize, usize), String> {
+    let size = grid
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+    match size {
+        Some((w, h)) if w > 0 && h > 0 => Ok((w, h)),
+        _ => Err(format!("expected a size such as 8x16, not {:?}", grid)),
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OverflowMode {
+    /// Break it after the last character that fits
+    Break,
+    /// Break it, ending the first part with a hyphen
+    Hyphen,
+    /// Kern or smush it, breaking it only if that's not enough
+    Shrink,
+    /// Cut it short with an ellipsis
+    Truncate,
+}
+
+impl From<OverflowMode> for Overflow {
+    fn from(mode: OverflowMode) -> Self {
+        match mode {
+            OverflowMode::Break => Overflow::Break,
+            OverflowMode::Hyphen => Overflow::Hyphenate,
+            OverflowMode::Shrink => Overflow::Shrink,
+            OverflowMode::Truncate => Overflow::Truncate,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum WrapMode {
+    /// Fit as many words as possible on each line
+    Greedy,
+    /// Even out the line lengths
+    Balanced,
+}
+
+impl From<WrapMode> for Wrap {
+    fn from(mode: WrapMode) -> Self {
+        match mode {
+            WrapMode::Greedy => Wrap::Greedy,
+            WrapMode::Balanced => Wrap::MinimumRaggedness,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PixelateMode {
+    /// Braille dots, two across and four down to a column
+    Braille,
+    /// Half blocks, two down to a column
+    Half,
+    /// Quadrant blocks, two across and two down to a column
+    Quad,
+}
+
+impl From<PixelateMode> for Pixelate {
+    fn from(mode: PixelateMode) -> Self {
+        match mode {
+            PixelateMode::Braille => Pixelate::Braille,
+            PixelateMode::Half => Pixelate::HalfBlocks,
+            PixelateMode::Quad => Pixelate::Quadrants,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MixRules {
+    /// The rules of the font given by --font
+    Base,
+    /// The loosest rules of all the fonts
+    Loosest,
+}
+
+impl From<MixRules> for Rules {
+    fn from(rules: MixRules) -> Self {
+        match rules {
+            MixRules::Base => Rules::Base,
+            MixRules::Loosest => Rules::Loosest,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum BorderStyle {
+    /// +, - and |
+    Ascii,
+    /// Light box drawing lines
+    Light,
+    /// Heavy box drawing lines
+    Heavy,
+    /// Double box drawing lines
+    Double,
+    /// Light box drawing lines with rounded corners
+    Rounded,
+    /// The font's own +, - and | characters
+    Font,
+}
+
+impl BorderStyle {
+    fn border(self, font: &Font) -> Border {
+        match self {
+            BorderStyle::Ascii => Border::new(Style::Ascii),
+            BorderStyle::Light => Border::new(Style::Light),
+            BorderStyle::Heavy => Border::new(Style::Heavy),
+            BorderStyle::Double => Border::new(Style::Double),
+            BorderStyle::Rounded => Border::new(Style::Rounded),
+            BorderStyle::Font => Border::from_font(font),
+        }
+    }
+}
+
+/// Prints art as it is rendered, or, when it has a border, keeps it to
+/// print inside the border at the end
+struct Output {
+    border: Option<Border>,
+    held: Vec<Rendered>,
+}
+
+impl Output {
+    fn print(&mut self, art: Rendered) {
+        match self.border {
+            Some(_) => self.held.push(art),
+            None => print!("{}", art),
+        }
+    }
+
+    fn finish(self) {
+        if let Some(border) = self.border {
+            let pieces: Vec<_> = self.held.iter().collect();
+            print!("{}", vstack(&pieces, 0, Position::Start).framed(&border));
+        }
+    }
+}
+
+impl Cli {
+    /// `art` laid out as the command line says
+    fn layout<'a>(&self, art: ArtOutput<'a>) -> ArtOutput<'a> {
+        art.deutsch(self.deutsch)
+            .overflow(self.overflow.into())
+            .wrap(self.wrap.into())
+            .scale(self.scale)
+            .pixelate(self.pixelate.map(Pixelate::from))
+            .bidi(self.bidi)
+    }
+
+    /// `stream` laid out as the command line says, but for bidi, which
+    /// streams can't do
+    fn stream_layout<'a, I: Iterator<Item = char>>(
+        &self,
+        stream: ArtStream<'a, I>,
+    ) -> ArtStream<'a, I> {
+        stream
+            .deutsch(self.deutsch)
+            .overflow(self.overflow.into())
+            .wrap(self.wrap.into())
+            .scale(self.scale)
+            .pixelate(self.pixelate.map(Pixelate::from))
+    }
+
+    fn direction(&self) -> PrintDirection {
+        if self.left_to_right {
+            PrintDirection::LeftToRight
+        } else if self.right_to_left {
+            PrintDirection::RightToLeft
+        } else {
+            PrintDirection::Font
+        }
+    }
 }
 
 fn find_font_dir() -> Option<PathBuf> {
@@ -60,29 +361,248 @@ fn read_font_from(dir: &Path, font: &str) -> Option<Font> {
     None
 }
 
+/// Reads each font named in markup runs once
+fn read_markup_fonts<'a>(
+    dir: &Path,
+    runs: &[(Option<&'a str>, String)],
+) -> Result<HashMap<&'a str, Font>, String> {
+    let mut fonts = HashMap::new();
+    for name in runs.iter().filter_map(|(name, _)| *name) {
+        if !fonts.contains_key(name) {
+            let font = read_font_from(dir, name).ok_or(format!("font {:?} not found", name))?;
+            fonts.insert(name, font);
+        }
+    }
+    Ok(fonts)
+}
+
+enum FontFormat {
+    FigFont,
+    Json,
+    Bitmap,
+}
+
+impl FontFormat {
+    fn of(path: &Path) -> Result<FontFormat, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("flf" | "tlf") => Ok(FontFormat::FigFont),
+            Some("json") => Ok(FontFormat::Json),
+            Some("bdf" | "psf") => Ok(FontFormat::Bitmap),
+            _ => Err(format!(
+                "{}: unknown font format, expected .flf, .tlf, .json, .bdf or .psf",
+                path.display()
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+fn read_json(path: &Path) -> Result<Font, Box<dyn Error>> {
+    let font = json::read_font(&fs::read_to_string(path)?)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(font)
+}
+
+#[cfg(feature = "json")]
+fn write_json(font: &Font) -> Result<String, Box<dyn Error>> {
+    Ok(json::write_font(font))
+}
+
+#[cfg(not(feature = "json"))]
+fn read_json(path: &Path) -> Result<Font, Box<dyn Error>> {
+    Err(format!("{}: built without JSON support", path.display()).into())
+}
+
+#[cfg(not(feature = "json"))]
+fn write_json(_: &Font) -> Result<String, Box<dyn Error>> {
+    Err("built without JSON support".into())
+}
+
+fn convert(input: &Path, output: &Path, pixels: Pixels) -> Result<(), Box<dyn Error>> {
+    let font = match FontFormat::of(input)? {
+        FontFormat::FigFont => read_font_file(input)?,
+        FontFormat::Json => read_json(input)?,
+        FontFormat::Bitmap => read_bitmap_file(input)?.to_font(pixels)?,
+    };
+    save(&font, output)
+}
+
+fn save(font: &Font, output: &Path) -> Result<(), Box<dyn Error>> {
+    let out = match FontFormat::of(output)? {
+        FontFormat::FigFont => write_font(font),
+        FontFormat::Json => write_json(font)?,
+        FontFormat::Bitmap => {
+            return Err(format!("{}: can't write bitmap fonts", output.display()).into())
+        }
+    };
+    fs::write(output, out)?;
+    Ok(())
+}
+
+fn font_from_image(
+    image: &Path,
+    grid: (usize, usize),
+    chars: &str,
+    pixels: Pixels,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let mut sheet = read_glyph_sheet(&fs::read(image)?, grid, chars)
+        .map_err(|e| format!("{}: {}", image.display(), e))?;
+    sheet.name = image
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into();
+    let font = sheet
+        .to_font(pixels)
+        .map_err(|e| format!("{}: {}", image.display(), e))?;
+    match output {
+        Some(output) => save(&font, output),
+        None => {
+            print!("{}", write_font(&font));
+            Ok(())
+        }
+    }
+}
+
+/// Prints `error` and exits
+fn fail(error: impl std::fmt::Display) -> ! {
+    eprintln!("rustlet: {}", error);
+    process::exit(1);
+}
+
+/// The lines of standard input, as they are read. Fails if one can't be
+/// read, such as when it isn't UTF-8.
+fn stdin_lines() -> impl Iterator<Item = String> {
+    io::stdin()
+        .lock()
+        .lines()
+        .map(|line| line.unwrap_or_else(|e| fail(e)))
+}
+
 fn main() {
     let cli = Cli::parse();
+    if let Some(command) = &cli.command {
+        let result = match command {
+            Command::Convert {
+                input,
+                output,
+                pixels,
+            } => convert(input, output, *pixels),
+            Command::FontFromImage {
+                image,
+                grid,
+                chars,
+                dark,
+                light,
+                output,
+            } => {
+                let pixels = Pixels::Chars {
+                    dark: *dark,
+                    light: *light,
+                };
+                font_from_image(image, *grid, chars, pixels, output.as_deref())
+            }
+        };
+        if let Err(e) = result {
+            fail(e);
+        }
+        return;
+    }
+    let direction = cli.direction();
 
     let rawfont = include_str!("../fonts/standard.flf");
 
     let fontdir = cli
         .fontdir
+        .clone()
         .or_else(find_font_dir)
         .unwrap_or(PathBuf::from("fonts"));
 
     let font = cli
         .font
-        .and_then(|f| read_font_from(&fontdir, &f))
-        .or_else(|| read_font(rawfont))
+        .as_deref()
+        .and_then(|f| read_font_from(&fontdir, f))
+        .or_else(|| read_font(rawfont).ok())
         .expect("No font readable");
 
-    let max_size: usize = cli.width as usize;
+    let settings = font.settings.with_direction(direction);
 
-    let message = cli.message.join(" ");
+    let border = cli.border.map(|style| {
+        let border = style
+            .border(&font)
+            .padding(Padding::uniform(cli.border_padding));
+        match cli.title.clone() {
+            Some(title) => border.title(title),
+            None => border,
+        }
+    });
+    // the border counts against the width, but always leaves a column
+    let max_size: Option<usize> = match cli.width {
+        _ if cli.no_wrap => None,
+        0 => None,
+        width => Some(border.as_ref().map_or(width as usize, |border| {
+            border.inner_width(width as usize).max(1)
+        })),
+    };
+    let mut output = Output {
+        border,
+        held: vec![],
+    };
 
-    let art_lines = art_lines(&message, &font, &font.settings, max_size);
+    if cli.markup {
+        let messages = if cli.message.is_empty() {
+            stdin_lines().collect()
+        } else {
+            vec![cli.message.join(" ")]
+        };
+        for markup in messages {
+            let runs = parse_markup(&markup);
+            let fonts = read_markup_fonts(&fontdir, &runs).unwrap_or_else(|e| fail(e));
+            let mix = Mix::from_runs(
+                &font,
+                runs.iter()
+                    .map(|(name, text)| (text.as_str(), name.map_or(&font, |name| &fonts[name]))),
+            );
+            let settings = mix.settings(cli.mix_rules.into()).with_direction(direction);
+            let art = cli
+                .layout(ArtOutput::mixed(&mix, &settings, max_size))
+                .render();
+            output.print(art);
+        }
+        output.finish();
+        return;
+    }
 
-    for art_line in art_lines {
-        print!("{}", art_line);
+    if cli.message.is_empty() && cli.bidi {
+        // The bidi algorithm needs a whole paragraph at a time
+        for line in stdin_lines() {
+            let art = cli
+                .layout(ArtOutput::build(&line, &font, &settings, max_size))
+                .render();
+            output.print(art);
+        }
+        output.finish();
+        return;
+    }
+
+    if cli.message.is_empty() {
+        // each input line ends an art line, as in figlet
+        let chars =
+            stdin_lines().flat_map(|line| line.chars().chain(iter::once('\n')).collect::<Vec<_>>());
+        let stream = cli.stream_layout(art_stream(chars, &font, &settings, max_size));
+        for art_line in stream {
+            output.print(art_line);
+        }
+        output.finish();
+        return;
     }
+
+    let message = cli.message.join(" ");
+
+    let art = cli
+        .layout(ArtOutput::build(&message, &font, &settings, max_size))
+        .render();
+    output.print(art);
+    output.finish();
 }
@@ -0,0 +1,286 @@
+//! Messages that switch fonts part way through.
+//!
+//! A `Mix` is a message made of runs of text, each in its own font.
+//! Each font is padded with blank rows above and below so that they are
+//! all as high and can be laid out on one line, lined up on their
+//! baselines unless `Mix::align` says otherwise. Lay a mix out with
+//! `ArtOutput::mixed`.
+//!
+//! Runs can also be written inline with `parse_markup`, such as
+//! `{font=slant}Hello {font=small}world`.
+
+use crate::font::Font;
+use crate::settings::{Settings, SmushMode};
+use crate::text::{Align, Frame};
+
+/// Which layout rules to join a mix of fonts with, as each font has its
+/// own
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rules {
+    /// the base font's, ignoring the others
+    Base,
+    /// the loosest of every font's: full width if any is laid out full
+    /// width, kerning if any is kerned or they have no smushing rule in
+    /// common, and otherwise smushing by the rules they share
+    #[default]
+    Loosest,
+}
+
+/// A message in several fonts. See the module documentation.
+pub struct Mix<'a> {
+    base: &'a Font,
+    text: String,
+    /// how many characters `text` has
+    length: usize,
+    /// the index in `text` of each run's first character, counted in
+    /// chars, and its font
+    runs: Vec<(usize, &'a Font)>,
+    align: Align,
+}
+
+/// The fonts of a mix, padded to the same height and hardblank
+pub(crate) struct Faces {
+    /// the base font first, then each other font once
+    fonts: Vec<Font>,
+    /// where each run starts, and the index of its font in `fonts`
+    runs: Vec<(usize, usize)>,
+}
+
+impl Faces {
+    pub(crate) fn base(&self) -> &Font {
+        &self.fonts[0]
+    }
+
+    /// The font of the character at `index`
+    pub(crate) fn at(&self, index: usize) -> &Font {
+        let run = self.runs.partition_point(|&(start, _)| start <= index);
+        match run.checked_sub(1) {
+            Some(run) => &self.fonts[self.runs[run].1],
+            None => self.base(),
+        }
+    }
+}
+
+/// The height of `font`, and how many rows down its baseline is
+fn extent(font: &Font) -> (usize, usize) {
+    (font.height() as usize, font.settings.baseline as usize)
+}
+
+impl<'a> Mix<'a> {
+    /// An empty message. `base` draws the spaces between words, and its
+    /// hardblank and print direction are used throughout.
+    pub fn new(base: &'a Font) -> Self {
+        Mix {
+            base,
+            text: String::new(),
+            length: 0,
+            runs: vec![],
+            align: Align::Baseline,
+        }
+    }
+
+    /// How to line up fonts of different heights
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Adds `text` in `font` to the end of the message
+    pub fn push(&mut self, text: &str, font: &'a Font) {
+        if text.is_empty() {
+            return;
+        }
+        self.runs.push((self.length, font));
+        self.text.push_str(text);
+        self.length += text.chars().count();
+    }
+
+    /// A message from runs of text, each with its font. `base` is as
+    /// for `new`.
+    pub fn from_runs<'b>(
+        base: &'a Font,
+        runs: impl IntoIterator<Item = (&'b str, &'a Font)>,
+    ) -> Self {
+        let mut mix = Mix::new(base);
+        for (text, font) in runs {
+            mix.push(text, font);
+        }
+        mix
+    }
+
+    /// The whole message, without markup
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn base(&self) -> &'a Font {
+        self.base
+    }
+
+    /// Each font used, the base font first, only once each
+    fn fonts(&self) -> Vec<&'a Font> {
+        let mut fonts = vec![self.base];
+        for &(_, font) in &self.runs {
+            if !fonts.iter().any(|seen| std::ptr::eq(*seen, font)) {
+                fonts.push(font);
+            }
+        }
+        fonts
+    }
+
+    /// How many blank rows go above and below `font`
+    fn padding(&self, font: &Font) -> (u32, u32) {
+        let frame = Frame::of(self.fonts().into_iter().map(extent));
+        let (height, baseline) = extent(font);
+        let (above, below) = frame.padding(height, baseline, self.align);
+        (above as u32, below as u32)
+    }
+
+    /// Settings to lay the mix out with: the base font's, as tall as the
+    /// fonts are once lined up, with layout rules chosen by `rules`
+    pub fn settings(&self, rules: Rules) -> Settings {
+        let (above, below) = self.padding(self.base);
+        let smushmode = match rules {
+            Rules::Base => self.base.settings.smushmode,
+            Rules::Loosest => {
+                // vertical layout is only the base font's to say
+                let vertical = self.base.settings.smushmode - SmushMode::from_bits_truncate(255);
+                loosest(self.fonts().iter().map(|f| f.settings.smushmode)) | vertical
+            }
+        };
+        Settings {
+            charheight: self.base.height() + above + below,
+            baseline: self.base.settings.baseline + above,
+            smushmode,
+            ..self.base.settings
+        }
+    }
+
+    /// Pads every font to line them up
+    pub(crate) fn faces(&self) -> Faces {
+        let fonts = self.fonts();
+        let hardblank = self.base.hardblank();
+        let runs = self
+            .runs
+            .iter()
+            .map(|&(start, font)| {
+                let index = fonts.iter().position(|f| std::ptr::eq(*f, font));
+                (start, index.expect("every font is listed"))
+            })
+            .collect();
+        let baseline = self.padding(self.base).0 + self.base.settings.baseline;
+        let fonts = fonts
+            .into_iter()
+            .map(|font| {
+                let (above, below) = self.padding(font);
+                font.padded(above, below, baseline, hardblank)
+            })
+            .collect();
+        Faces { fonts, runs }
+    }
+}
+
+/// The loosest horizontal layout that each of `modes` allows. See
+/// `Rules::Loosest`.
+fn loosest(modes: impl IntoIterator<Item = SmushMode>) -> SmushMode {
+    let rules_mask = SmushMode::from_bits_truncate(63);
+    let mut kern = false;
+    // the rules shared by every font that smushes by rules
+    let mut shared: Option<SmushMode> = None;
+    for mode in modes {
+        if !mode.intersects(SmushMode::SMUSH | SmushMode::KERN) {
+            return SmushMode::empty();
+        }
+        if !mode.contains(SmushMode::SMUSH) {
+            kern = true;
+            continue;
+        }
+        let rules = mode & rules_mask;
+        if !rules.is_empty() {
+            shared = Some(shared.map_or(rules, |shared| shared & rules));
+        }
+    }
+    match shared {
+        _ if kern => SmushMode::KERN,
+        Some(rules) if rules.is_empty() => SmushMode::KERN,
+        rules => SmushMode::SMUSH | rules.unwrap_or_default(),
+    }
+}
+
+/// Splits a message into runs at `{font=NAME}` tags, which switch to
+/// the font called NAME. `{font}` switches back to the base font, shown
+/// as `None`, as does the start of the message. `{{` stands for `{`;
+/// anything else is left as it is.
+pub fn parse_markup(markup: &str) -> Vec<(Option<&str>, String)> {
+    let mut runs = vec![];
+    let mut font = None;
+    let mut text = String::new();
+    let mut rest = markup;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+        if let Some(after) = rest.strip_prefix("{{") {
+            text.push('{');
+            rest = after;
+            continue;
+        }
+        let tag = rest[1..].split_once('}').and_then(|(tag, after)| {
+            let name = match tag.strip_prefix("font") {
+                Some("") => None,
+                Some(name) => Some(name.strip_prefix('=').filter(|n| !n.is_empty())?),
+                None => return None,
+            };
+            Some((name, after))
+        });
+        match tag {
+            Some((name, after)) => {
+                if !text.is_empty() {
+                    runs.push((font, std::mem::take(&mut text)));
+                }
+                font = name;
+                rest = after;
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        runs.push((font, text));
+    }
+    runs
+}
+
+#[test]
+fn parse_markup_runs() {
+    assert_eq!(
+        parse_markup("{font=slant}Hello {font=small}world{font}!"),
+        [
+            (Some("slant"), "Hello ".to_string()),
+            (Some("small"), "world".to_string()),
+            (None, "!".to_string()),
+        ]
+    );
+    assert_eq!(
+        parse_markup("a {{font=x} {b} {font=}{font"),
+        [(None, "a {font=x} {b} {font=}{font".to_string())]
+    );
+    assert_eq!(parse_markup("{font=x}"), []);
+}
+
+#[test]
+fn loosest_rules() {
+    let smush = |bits: u32| SmushMode::SMUSH | SmushMode::from_bits_truncate(bits);
+    assert_eq!(loosest([smush(1 | 4), smush(4 | 8)]), smush(4));
+    assert_eq!(loosest([smush(1), smush(2)]), SmushMode::KERN);
+    // universal overlapping gives way to any rules
+    assert_eq!(loosest([smush(0), smush(2)]), smush(2));
+    assert_eq!(loosest([smush(0), smush(0)]), smush(0));
+    assert_eq!(loosest([smush(1), SmushMode::KERN]), SmushMode::KERN);
+    assert_eq!(
+        loosest([SmushMode::KERN, SmushMode::empty()]),
+        SmushMode::empty()
+    );
+}
@@ -0,0 +1,171 @@
+//! Reading black and white (PBM) and greyscale (PGM) netpbm images, in
+//! both their plain and raw forms
+
+use crate::font::FontError;
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take, take_till},
+    character::complete::{digit1, multispace0, multispace1, one_of},
+    combinator::{map, map_res, value},
+    multi::{count, many0},
+    number::complete::{be_u16, u8 as byte},
+    sequence::{pair, preceded, terminated},
+    IResult,
+};
+
+/// An image as whether each pixel is dark, row by row from the top
+pub(crate) type Image = Vec<Vec<bool>>;
+
+/// The most pixels across or down an image may have
+const MAX_SIZE: usize = 1 << 16;
+/// The most pixels an image may have in all
+const MAX_PIXELS: usize = 1 << 26;
+
+/// Skips whitespace and comments
+fn blank(input: &[u8]) -> IResult<&[u8], ()> {
+    value(
+        (),
+        many0(alt((
+            value((), multispace1),
+            value((), pair(tag("#"), take_till(|c| c == b'\n' || c == b'\r'))),
+        ))),
+    )(input)
+}
+
+fn number(input: &[u8]) -> IResult<&[u8], usize> {
+    preceded(
+        blank,
+        map_res(digit1, |digits: &[u8]| {
+            std::str::from_utf8(digits).unwrap_or_default().parse()
+        }),
+    )(input)
+}
+
+/// Reads the numbers of a plain image, `count` of them
+fn plain(input: &[u8], pixels: usize) -> IResult<&[u8], Vec<usize>> {
+    count(number, pixels)(input)
+}
+
+/// Reads the pixels of a plain PBM, which needn't be separated
+fn plain_bits(input: &[u8], pixels: usize) -> IResult<&[u8], Vec<bool>> {
+    count(
+        preceded(multispace0, map(one_of("01"), |bit| bit == '1')),
+        pixels,
+    )(input)
+}
+
+/// Reads the samples of a raw PGM, which are two bytes each if the
+/// maximum is over 255
+fn raw_samples(input: &[u8], pixels: usize, max: usize) -> IResult<&[u8], Vec<usize>> {
+    if max > 255 {
+        count(map(be_u16, usize::from), pixels)(input)
+    } else {
+        count(map(byte, usize::from), pixels)(input)
+    }
+}
+
+fn rows(pixels: Vec<bool>, width: usize) -> Image {
+    pixels.chunks(width).map(<[bool]>::to_vec).collect()
+}
+
+/// Reads the kind of image, its width and height, and the value of
+/// white for greyscale images
+fn header(input: &[u8]) -> IResult<&[u8], (char, usize, usize, usize)> {
+    let (input, kind) = preceded(tag("P"), one_of("1245"))(input)?;
+    let (input, width) = number(input)?;
+    let (mut input, height) = number(input)?;
+    let mut max = 1;
+    if kind == '2' || kind == '5' {
+        (input, max) = number(input)?;
+    }
+    Ok((input, (kind, width, height, max)))
+}
+
+/// Reads the pixels of an image `width` pixels across and `pixels` in
+/// all
+fn raster(
+    input: &[u8],
+    kind: char,
+    width: usize,
+    pixels: usize,
+    max: usize,
+) -> IResult<&[u8], Image> {
+    // A grey is dark if it's nearer black than white
+    let dark = |grey: usize| grey.saturating_mul(2) < max;
+    match kind {
+        '1' => map(|input| plain_bits(input, pixels), |bits| rows(bits, width))(input),
+        '2' => map(
+            |input| plain(input, pixels),
+            |greys| rows(greys.into_iter().map(dark).collect(), width),
+        )(input),
+        // the raster follows a single whitespace character
+        '4' => {
+            let row_size = width.div_ceil(8);
+            let height = pixels / width;
+            let (input, raster) = preceded(take(1usize), take(row_size * height))(input)?;
+            let image = raster
+                .chunks(row_size)
+                .map(|row| {
+                    (0..width)
+                        .map(|column| row[column / 8] & (0x80 >> (column % 8)) != 0)
+                        .collect()
+                })
+                .collect();
+            Ok((input, image))
+        }
+        _ => map(
+            preceded(take(1usize), |input| raw_samples(input, pixels, max)),
+            |greys| rows(greys.into_iter().map(dark).collect(), width),
+        )(input),
+    }
+}
+
+/// Reads a PBM or PGM image, plain or raw
+pub(crate) fn read_image(data: &[u8]) -> Result<Image, FontError> {
+    let syntax = |message: &str| FontError::Syntax(message.to_string());
+    let cut_short = || syntax("not a PBM or PGM image, or cut short");
+    let (input, (kind, width, height, max)) = header(data).map_err(|_| cut_short())?;
+    let pixels = width
+        .checked_mul(height)
+        .filter(|&pixels| {
+            (1..=MAX_SIZE).contains(&width)
+                && (1..=MAX_SIZE).contains(&height)
+                && pixels <= MAX_PIXELS
+        })
+        .ok_or_else(|| syntax("image is empty or too large"))?;
+    let image = terminated(move |input| raster(input, kind, width, pixels, max), blank)(input);
+    image.map(|(_, image)| image).map_err(|_| cut_short())
+}
+
+#[test]
+fn read_pbm() {
+    let plain = b"P1\n# a comment\n3 2\n1 0 1\n010\n";
+    let expected = vec![vec![true, false, true], vec![false, true, false]];
+    assert_eq!(read_image(plain), Ok(expected.clone()));
+    let raw = b"P4 3 2\n\xA0\x40";
+    assert_eq!(read_image(raw), Ok(expected));
+    assert!(read_image(b"P1 3 2 1 0 1").is_err());
+}
+
+#[test]
+fn reject_sizes() {
+    let size_error = Err(FontError::Syntax("image is empty or too large".to_string()));
+    assert_eq!(read_image(b"P1 99999999999 99999999999\n"), size_error);
+    assert_eq!(read_image(b"P4 99999999999 99999999999\n"), size_error);
+    assert_eq!(read_image(b"P1 0 99999\n"), size_error);
+    assert_eq!(
+        read_image(b"P2 1 1 99999999999999\n99999999999999\n"),
+        Ok(vec![vec![false]])
+    );
+}
+
+#[test]
+fn read_pgm() {
+    let expected = vec![vec![true, false], vec![false, true]];
+    let plain = b"P2 2 2 255\n0 200\n128 127\n";
+    assert_eq!(read_image(plain), Ok(expected.clone()));
+    let raw = b"P5 2 2 255\n\x00\xC8\x80\x7F";
+    assert_eq!(read_image(raw), Ok(expected.clone()));
+    let wide = b"P5 2 2 1000\n\x00\x00\x03\xE8\x01\xF4\x01\xF3";
+    assert_eq!(read_image(wide), Ok(expected));
+}
@@ -0,0 +1,144 @@
+use crate::grid::Row;
+use crate::text::{Span, Text};
+
+/// Redraws art in terminal graphics, treating every sub-character but
+/// spaces and hardblanks as ink and packing several of them into each
+/// cell, so that large fonts fit narrow terminals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pixelate {
+    /// a column of two to a cell, drawn with `▀`, `▄` and `█`
+    HalfBlocks,
+    /// two by two to a cell, drawn with quadrant blocks such as `▚`
+    Quadrants,
+    /// two across and four down to a cell, drawn with braille dots
+    Braille,
+}
+
+/// Quadrant blocks by which quarters are inked: upper left, upper
+/// right, lower left and lower right, from the lowest bit
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// The bit of each braille dot, left to right then top to bottom
+const BRAILLE_DOTS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+impl Pixelate {
+    /// How many sub-characters across and down each cell holds
+    pub fn cell_size(&self) -> (usize, usize) {
+        match self {
+            Pixelate::HalfBlocks => (1, 2),
+            Pixelate::Quadrants => (2, 2),
+            Pixelate::Braille => (2, 4),
+        }
+    }
+
+    /// Draws a cell, given which of its sub-characters are ink as bits,
+    /// left to right then top to bottom from the lowest
+    fn draw(&self, ink: u32) -> char {
+        if ink == 0 {
+            return ' ';
+        }
+        match self {
+            Pixelate::HalfBlocks => [' ', '▀', '▄', '█'][ink as usize],
+            Pixelate::Quadrants => QUADRANTS[ink as usize],
+            Pixelate::Braille => {
+                let dots = BRAILLE_DOTS
+                    .iter()
+                    .enumerate()
+                    .filter(|(bit, _)| ink & (1 << bit) != 0)
+                    .fold(0, |dots, (_, dot)| dots | dot);
+                char::from_u32(0x2800 + dots).expect("braille patterns are characters")
+            }
+        }
+    }
+}
+
+impl Text {
+    /// This art redrawn in terminal graphics. Spans cover every cell
+    /// their columns had a part in.
+    pub fn pixelate(&self, mode: Pixelate, hardblank: char) -> Text {
+        let (across, down) = mode.cell_size();
+        let width = self.width().div_ceil(across);
+        let is_ink = |row: &Row, column: usize| {
+            row.get(column)
+                .is_some_and(|&ch| ch != ' ' && ch != hardblank)
+        };
+        let art = self
+            .art
+            .chunks(down)
+            .map(|rows| {
+                (0..width)
+                    .map(|cell| {
+                        let mut ink = 0;
+                        for (y, row) in rows.iter().enumerate() {
+                            for x in 0..across {
+                                if is_ink(row, cell * across + x) {
+                                    ink |= 1 << (y * across + x);
+                                }
+                            }
+                        }
+                        mode.draw(ink)
+                    })
+                    .collect()
+            })
+            .collect();
+        let spans = self
+            .spans
+            .iter()
+            .map(|span| Span {
+                index: span.index,
+                columns: span.columns.start / across..span.columns.end.div_ceil(across),
+            })
+            .collect();
+        Text {
+            art,
+            text: self.text.clone(),
+            spans,
+            baseline: self.baseline.div_ceil(down),
+        }
+    }
+}
+
+#[test]
+fn pixelate_half_blocks() {
+    let art = Text::from_rows(&["#$ #", " #_#", "/  \\"]);
+    let pixelated = art.pixelate(Pixelate::HalfBlocks, '$');
+    assert_eq!(pixelated.display_rows(), ["▀▄▄█", "▀  ▀"]);
+}
+
+#[test]
+fn pixelate_quadrants() {
+    let art = Text::from_rows(&["# #", " ##", "#"]);
+    let pixelated = art.pixelate(Pixelate::Quadrants, '$');
+    assert_eq!(pixelated.display_rows(), ["▚▌", "▘ "]);
+}
+
+#[test]
+fn pixelate_braille() {
+    let art = Text::from_rows(&["#.", ".#", "  ", "##", "#"]);
+    let pixelated = art.pixelate(Pixelate::Braille, '$');
+    assert_eq!(pixelated.display_rows(), ["⣛", "⠁"]);
+}
+
+#[test]
+fn pixelate_spans() {
+    let mut art = Text::from_rows(&["aabbbcc"]);
+    art.spans = vec![
+        Span {
+            index: 0,
+            columns: 0..2,
+        },
+        Span {
+            index: 1,
+            columns: 2..5,
+        },
+        Span {
+            index: 2,
+            columns: 5..7,
+        },
+    ];
+    let pixelated = art.pixelate(Pixelate::Braille, '$');
+    let columns: Vec<_> = pixelated.spans.iter().map(|s| s.columns.clone()).collect();
+    assert_eq!(columns, [0..1, 1..3, 2..4]);
+}
@@ -0,0 +1,274 @@
+use crate::grid::CONTINUATION;
+use crate::text::{Span, Text};
+use std::fmt;
+use std::ops::Range;
+
+/// A single position in the rendered output grid.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cell {
+    /// the sub-character shown at this position, hardblanks already
+    /// replaced with spaces
+    pub ch: char,
+}
+
+impl Cell {
+    pub fn new(ch: char) -> Self {
+        Cell { ch }
+    }
+
+    /// Whether this cell is the second column of a double-width
+    /// sub-character, printed as part of the cell before it
+    pub fn is_continuation(&self) -> bool {
+        self.ch == CONTINUATION
+    }
+}
+
+/// Bookkeeping for one art line: which rows of the grid belong to it,
+/// and the unadorned text it was rendered from.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LineInfo {
+    pub(crate) rows: Range<usize>,
+    pub(crate) text: String,
+    pub(crate) spans: Vec<Span>,
+}
+
+/// The result of rendering a message with a font: a grid of cells,
+/// the boundaries of each art line within it, and the source text.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RenderedParts"))]
+pub struct Rendered {
+    pub(crate) cells: Vec<Vec<Cell>>,
+    pub(crate) lines: Vec<LineInfo>,
+    pub(crate) source: String,
+}
+
+/// A render result as it is serialized, checked when it is read back so
+/// that its art lines are all within its grid
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenderedParts {
+    cells: Vec<Vec<Cell>>,
+    lines: Vec<LineInfo>,
+    source: String,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RenderedParts> for Rendered {
+    type Error = String;
+
+    fn try_from(parts: RenderedParts) -> Result<Rendered, String> {
+        for line in &parts.lines {
+            let rows = parts
+                .cells
+                .get(line.rows.clone())
+                .ok_or_else(|| format!("art line rows {:?} out of range", line.rows))?;
+            let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+            if let Some(span) = line
+                .spans
+                .iter()
+                .find(|span| span.columns.start > span.columns.end || span.columns.end > width)
+            {
+                return Err(format!("span columns {:?} out of range", span.columns));
+            }
+        }
+        Ok(Rendered {
+            cells: parts.cells,
+            lines: parts.lines,
+            source: parts.source,
+        })
+    }
+}
+
+/// A borrowed view of a single art line within a `Rendered`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ArtLine<'a> {
+    rows: &'a [Vec<Cell>],
+    text: &'a str,
+    spans: &'a [Span],
+}
+
+impl<'a> ArtLine<'a> {
+    /// The rows of cells making up this art line
+    pub fn rows(&self) -> &'a [Vec<Cell>] {
+        self.rows
+    }
+
+    /// The unadorned text this art line was rendered from
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The columns produced by each input character, left to right.
+    /// Neighbouring spans overlap where characters were smushed.
+    pub fn spans(&self) -> &'a [Span] {
+        self.spans
+    }
+
+    /// Indices of the input characters drawn at `column`. Smushed
+    /// columns belong to both of the characters that share them.
+    pub fn hit_test(&self, column: usize) -> Vec<usize> {
+        self.spans
+            .iter()
+            .filter(|span| span.contains(column))
+            .map(|span| span.index)
+            .collect()
+    }
+
+    pub fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+}
+
+impl fmt::Display for ArtLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut line = String::new();
+        for row in self.rows {
+            line.clear();
+            line.extend(
+                row.iter()
+                    .filter(|cell| !cell.is_continuation())
+                    .map(|cell| cell.ch),
+            );
+            line.push('\n');
+            f.write_str(&line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Rendered {
+    /// Builds a render result from laid-out art lines, replacing
+    /// `hardblank` with a space.
+    pub fn from_texts(texts: Vec<Text>, hardblank: char, source: &str) -> Self {
+        let mut cells = vec![];
+        let mut lines = vec![];
+        for text in texts {
+            let start = cells.len();
+            for row in text.art.iter() {
+                let row = row
+                    .iter()
+                    .map(|&ch| Cell::new(if ch == hardblank { ' ' } else { ch }))
+                    .collect();
+                cells.push(row);
+            }
+            lines.push(LineInfo {
+                rows: start..cells.len(),
+                text: text.text,
+                spans: text.spans,
+            });
+        }
+        Rendered {
+            cells,
+            lines,
+            source: source.to_string(),
+        }
+    }
+
+    /// All rows of the output, top to bottom
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.cells
+    }
+
+    /// The message that was rendered
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Width of the widest row
+    pub fn width(&self) -> usize {
+        self.cells.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Total number of rows, across all art lines
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Iterates over the art lines, in order
+    pub fn art_lines(&self) -> impl Iterator<Item = ArtLine<'_>> {
+        self.lines.iter().map(|line| ArtLine {
+            rows: &self.cells[line.rows.clone()],
+            text: &line.text,
+            spans: &line.spans,
+        })
+    }
+
+    /// Returns the art line at `index`, if any
+    pub fn art_line(&self, index: usize) -> Option<ArtLine<'_>> {
+        self.art_lines().nth(index)
+    }
+
+    /// Indices of the input characters drawn at `row` and `column` of
+    /// the whole output
+    pub fn hit_test(&self, row: usize, column: usize) -> Vec<usize> {
+        self.lines
+            .iter()
+            .zip(self.art_lines())
+            .find(|(info, _)| info.rows.contains(&row))
+            .map(|(_, line)| line.hit_test(column))
+            .unwrap_or_default()
+    }
+
+    /// Finds where the input character at `index` was drawn, returning
+    /// the rows and columns it covers. Whitespace that ended up at a
+    /// line break is not drawn anywhere.
+    pub fn locate(&self, index: usize) -> Option<(Range<usize>, Range<usize>)> {
+        self.lines.iter().find_map(|line| {
+            line.spans
+                .iter()
+                .find(|span| span.index == index)
+                .map(|span| (line.rows.clone(), span.columns.clone()))
+        })
+    }
+
+    /// Returns each art line as a `String`, each of which can span
+    /// multiple lines of output.
+    pub fn to_lines(&self) -> Vec<String> {
+        self.art_lines().map(|line| line.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+impl Rendered {
+    /// One art line of `rows`, for tests, drawn from `source` with each
+    /// of its characters in a column of its own
+    pub(crate) fn from_rows(rows: &[&str], source: &str) -> Rendered {
+        let text = Text {
+            text: source.to_string(),
+            spans: (0..source.chars().count())
+                .map(|index| Span {
+                    index,
+                    columns: index..index + 1,
+                })
+                .collect(),
+            ..Text::from_rows(rows)
+        };
+        Rendered::from_texts(vec![text], '$', source)
+    }
+
+    /// The rows of the art as they are displayed, for tests
+    pub(crate) fn display_rows(&self) -> Vec<String> {
+        self.to_string().lines().map(String::from).collect()
+    }
+}
+
+impl fmt::Display for Rendered {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.art_lines() {
+            write!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
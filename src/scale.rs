@@ -0,0 +1,274 @@
+use crate::grid::{is_wide, Row, CONTINUATION};
+use crate::text::{Span, Text};
+use std::fmt;
+use std::str::FromStr;
+
+/// The biggest numerator or denominator a `Factor` can have
+pub const MAX_FACTOR: usize = 64;
+
+/// How many times as big to draw art along one axis, as a fraction.
+/// Less than one shrinks it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Factor {
+    numerator: usize,
+    denominator: usize,
+}
+
+/// `a * b / c`, rounded up or down, or `usize::MAX` if that is too big
+fn mul_div(a: usize, b: usize, c: usize, round_up: bool) -> usize {
+    let (product, c) = (a as u128 * b as u128, c as u128);
+    let quotient = match round_up {
+        true => product.div_ceil(c),
+        false => product / c,
+    };
+    usize::try_from(quotient).unwrap_or(usize::MAX)
+}
+
+impl Factor {
+    /// `numerator / denominator`, if both are between 1 and
+    /// `MAX_FACTOR`
+    pub fn new(numerator: usize, denominator: usize) -> Option<Self> {
+        let valid = 1..=MAX_FACTOR;
+        if valid.contains(&numerator) && valid.contains(&denominator) {
+            Some(Factor {
+                numerator,
+                denominator,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn numerator(&self) -> usize {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> usize {
+        self.denominator
+    }
+
+    /// How long `length` sub-characters become, rounding up
+    pub fn apply(&self, length: usize) -> usize {
+        mul_div(length, self.numerator, self.denominator, true)
+    }
+
+    /// The longest that fits in `length` once scaled
+    pub fn fitting(&self, length: usize) -> usize {
+        mul_div(length, self.denominator, self.numerator, false)
+    }
+
+    /// Where sub-character `i` starts once scaled, rounding down
+    fn start(&self, i: usize) -> usize {
+        mul_div(i, self.numerator, self.denominator, false)
+    }
+
+    /// The sub-characters that output position `i` is drawn from
+    fn source(&self, i: usize) -> std::ops::Range<usize> {
+        let start = self.fitting(i);
+        let end = self
+            .fitting(i.saturating_add(1))
+            .max(start.saturating_add(1));
+        start..end
+    }
+}
+
+/// `n` or `n/d`, each at most `MAX_FACTOR`
+impl FromStr for Factor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (numerator, denominator) = s.split_once('/').unwrap_or((s, "1"));
+        match (numerator.parse(), denominator.parse()) {
+            (Ok(numerator), Ok(denominator)) => {
+                Factor::new(numerator, denominator).ok_or_else(|| {
+                    format!(
+                        "expected a factor with parts from 1 to {}, not {:?}",
+                        MAX_FACTOR, s
+                    )
+                })
+            }
+            _ => Err(format!("expected a factor such as 2 or 1/2, not {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Factor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+/// How much bigger to draw art, across and down. Enlarging repeats each
+/// sub-character; shrinking draws each cell with the commonest of the
+/// sub-characters it stands for, ignoring blanks unless it is all
+/// blank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scale {
+    pub across: Factor,
+    pub down: Factor,
+}
+
+/// `AxD`, such as `2x1` or `1/2x1/2`
+impl FromStr for Scale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (across, down) = s
+            .split_once(['x', 'X'])
+            .ok_or_else(|| format!("expected a scale such as 2x1, not {:?}", s))?;
+        Ok(Scale {
+            across: across.parse()?,
+            down: down.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.across, self.down)
+    }
+}
+
+/// The commonest sub-character drawn in `block`, or its first if it
+/// is all blank. Ties go to the first.
+fn representative<'a>(block: impl Iterator<Item = &'a char>, hardblank: char) -> char {
+    let mut counts: Vec<(char, usize)> = vec![];
+    let mut first = None;
+    for &ch in block {
+        first.get_or_insert(ch);
+        if ch == ' ' || ch == hardblank || ch == CONTINUATION {
+            continue;
+        }
+        match counts.iter_mut().find(|(seen, _)| *seen == ch) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((ch, 1)),
+        }
+    }
+    let commonest = counts.iter().rev().max_by_key(|(_, count)| *count);
+    commonest.map_or(first.unwrap_or(' '), |(ch, _)| *ch)
+}
+
+/// Puts a continuation after each double-width sub-character, and
+/// repeats or drops ones that scaling split up
+fn mend_wide(row: &mut [char]) {
+    let mut last_wide = ' ';
+    let mut i = 0;
+    while i < row.len() {
+        let ch = match row[i] {
+            CONTINUATION => last_wide,
+            ch => ch,
+        };
+        if is_wide(ch) {
+            if i + 1 < row.len() {
+                row[i] = ch;
+                row[i + 1] = CONTINUATION;
+                last_wide = ch;
+                i += 2;
+                continue;
+            }
+            row[i] = ' ';
+        } else {
+            row[i] = ch;
+        }
+        i += 1;
+    }
+}
+
+impl Text {
+    /// This art drawn bigger or smaller. See `Scale`. Spans cover every
+    /// column their columns became.
+    pub fn scale(&self, scale: Scale, hardblank: char) -> Text {
+        let Scale { across, down } = scale;
+        let width = across.apply(self.width());
+        let art = (0..down.apply(self.height()))
+            .map(|y| {
+                let source = down.source(y);
+                let rows = &self.art[source.start..source.end.min(self.height())];
+                let mut row: Vec<char> = (0..width)
+                    .map(|x| {
+                        let columns = across.source(x);
+                        let block = rows.iter().flat_map(|row| {
+                            let end = columns.end.min(row.len());
+                            row.get(columns.start..end).unwrap_or_default()
+                        });
+                        representative(block, hardblank)
+                    })
+                    .collect();
+                mend_wide(&mut row);
+                Row::from(row)
+            })
+            .collect();
+        let spans = self
+            .spans
+            .iter()
+            .map(|span| Span {
+                index: span.index,
+                columns: across.start(span.columns.start)..across.apply(span.columns.end),
+            })
+            .collect();
+        Text {
+            art,
+            text: self.text.clone(),
+            spans,
+            baseline: down.apply(self.baseline),
+        }
+    }
+}
+
+#[test]
+fn scale_up() {
+    let art = Text::from_rows(&["/\\", "$_"]);
+    let scale: Scale = "2x3".parse().unwrap();
+    assert_eq!(
+        art.scale(scale, '$').display_rows(),
+        ["//\\\\", "//\\\\", "//\\\\", "$$__", "$$__", "$$__"]
+    );
+}
+
+#[test]
+fn scale_down() {
+    let art = Text::from_rows(&["##  ", "#.  ", "|   ", "|  $"]);
+    let scale: Scale = "1/2x1/2".parse().unwrap();
+    assert_eq!(art.scale(scale, '$').display_rows(), ["# ", "| "]);
+    // odd sizes round up
+    let art = Text::from_rows(&["abc", "def", "ghi"]);
+    assert_eq!(art.scale(scale, '$').display_rows(), ["ac", "gi"]);
+}
+
+#[test]
+fn scale_wide() {
+    let art = Text::from_rows(&["漢a"]);
+    assert_eq!(
+        art.scale("2x1".parse().unwrap(), '$').display_rows(),
+        ["漢漢aa"]
+    );
+    assert_eq!(
+        art.scale("3/2x1".parse().unwrap(), '$').display_rows(),
+        ["漢漢a"]
+    );
+}
+
+#[test]
+fn scale_parse() {
+    let scale: Scale = "3/2x1".parse().unwrap();
+    assert_eq!(scale.across.apply(4), 6);
+    assert_eq!(scale.across.fitting(7), 4);
+    assert_eq!(scale.to_string(), "3/2x1");
+    assert!("2".parse::<Scale>().is_err());
+    assert!("0x1".parse::<Scale>().is_err());
+    assert!("2x1/0".parse::<Scale>().is_err());
+    assert!("65x1".parse::<Scale>().is_err());
+    assert!("1x1/100000".parse::<Scale>().is_err());
+    assert_eq!(Factor::new(0, 1), None);
+    let most = Factor::new(MAX_FACTOR, 1).unwrap();
+    assert_eq!(most.apply(usize::MAX), usize::MAX);
+    assert_eq!(most.fitting(usize::MAX), usize::MAX / MAX_FACTOR);
+    assert_eq!(
+        Factor::new(1, MAX_FACTOR).unwrap().fitting(usize::MAX),
+        usize::MAX
+    );
+}
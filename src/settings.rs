@@ -35,6 +35,53 @@ impl From<SmushMode> for u32 {
     }
 }
 
+/// Each layout rule and its name, as used when serializing
+#[cfg(feature = "serde")]
+const RULE_NAMES: [(SmushMode, &str); 15] = [
+    (SmushMode::EQUAL, "equal"),
+    (SmushMode::LOWLINE, "lowline"),
+    (SmushMode::HIERARCHY, "hierarchy"),
+    (SmushMode::PAIR, "pair"),
+    (SmushMode::BIGX, "bigx"),
+    (SmushMode::HARDBLANK, "hardblank"),
+    (SmushMode::KERN, "kern"),
+    (SmushMode::SMUSH, "smush"),
+    (SmushMode::VERT_EQUAL, "vert_equal"),
+    (SmushMode::VERT_LOWLINE, "vert_lowline"),
+    (SmushMode::VERT_HIERARCHY, "vert_hierarchy"),
+    (SmushMode::VERT_PAIR, "vert_pair"),
+    (SmushMode::VERT_SUPER_SMUSH, "vert_super_smush"),
+    (SmushMode::VERT_FIT, "vert_fit"),
+    (SmushMode::VERT_SMUSH, "vert_smush"),
+];
+
+/// Serializes as the list of rule names, e.g. `["equal", "smush"]`
+#[cfg(feature = "serde")]
+impl serde::Serialize for SmushMode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            RULE_NAMES
+                .iter()
+                .filter(|(rule, _)| self.contains(*rule))
+                .map(|(_, name)| name),
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SmushMode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        names.iter().try_fold(SmushMode::empty(), |mode, name| {
+            RULE_NAMES
+                .iter()
+                .find(|(_, rule_name)| rule_name == name)
+                .map(|(rule, _)| mode | *rule)
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown layout rule {:?}", name)))
+        })
+    }
+}
+
 impl SmushMode {
     pub fn from_old_layout(bits: i32) -> Self {
         match bits {
@@ -47,9 +94,32 @@ impl SmushMode {
             }
         }
     }
+
+    /// The old layout header value with the same horizontal layout, as
+    /// far as it can say: universal smushing becomes kerning
+    pub fn to_old_layout(self) -> i32 {
+        if self.contains(SmushMode::SMUSH) {
+            (self & SmushMode::from_bits_truncate(63)).bits() as i32
+        } else if self.contains(SmushMode::KERN) {
+            0
+        } else {
+            -1
+        }
+    }
+}
+
+/// Which way to lay out text, like figlet's -L, -R and -X
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PrintDirection {
+    /// whatever the font asks for
+    #[default]
+    Font,
+    LeftToRight,
+    RightToLeft,
 }
 
-#[derive(Debug, PartialEq, Default)]
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Settings {
     pub hardblank: char,
     pub charheight: u32,
@@ -72,4 +142,17 @@ impl Settings {
     pub fn is_universal_overlap(&self) -> bool {
         !self.smushmode.intersects(SmushMode::from_bits_truncate(63))
     }
+
+    /// These settings with the print direction overridden
+    pub fn with_direction(&self, direction: PrintDirection) -> Settings {
+        let right2left = match direction {
+            PrintDirection::Font => self.right2left,
+            PrintDirection::LeftToRight => false,
+            PrintDirection::RightToLeft => true,
+        };
+        Settings {
+            right2left,
+            ..*self
+        }
+    }
 }
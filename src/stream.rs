@@ -0,0 +1,558 @@
+use crate::font::{self, Font};
+use crate::mix::Faces;
+use crate::pixelate::Pixelate;
+use crate::render::Rendered;
+use crate::scale::Scale;
+use crate::settings::{Settings, SmushMode};
+use crate::text::Text;
+use crate::wrap::{self, append_word, Word, Wrap};
+use std::collections::VecDeque;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// What to do with a word too wide for a line of its own
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// break it after the last character that fits
+    #[default]
+    Break,
+    /// break it, ending the first part with a hyphen
+    Hyphenate,
+    /// lay it out tighter, kerning or smushing it if the font doesn't
+    /// already, and break it only if that's not enough
+    Shrink,
+    /// cut it short, ending it with an ellipsis, and drop the rest
+    Truncate,
+}
+
+/// Layout options shared by the batch and streaming renderers
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Options {
+    pub(crate) deutsch: bool,
+    pub(crate) bidi: bool,
+    pub(crate) overflow: Overflow,
+    pub(crate) wrap: Wrap,
+    pub(crate) scale: Option<Scale>,
+    pub(crate) pixelate: Option<Pixelate>,
+}
+
+/// Incremental layout engine. Consumes characters, each with its index
+/// in the input, one at a time, gathering them into grapheme clusters
+/// and appending those in place to the word and line being built, and
+/// yields each art line (justified, hardblanks intact) as soon as the
+/// next word no longer fits on it, or a newline ends it.
+pub(crate) struct Layout<'a, I> {
+    chars: I,
+    font: &'a Font,
+    /// the font of each character, when they differ
+    faces: Option<&'a Faces>,
+    settings: &'a Settings,
+    /// `None` for unlimited width: never wrap
+    max_width: Option<usize>,
+    options: Options,
+    /// the grapheme cluster being read, and the index of its first
+    /// character
+    grapheme: String,
+    grapheme_index: usize,
+    /// the art line being filled
+    line: Text,
+    /// the word, or piece of a word too long to fit, being built
+    word: Text,
+    /// the glyphs making up `word`, and the index of each one's input
+    pieces: Vec<(usize, &'a Text)>,
+    /// the settings `word` is laid out with, tighter than the font's
+    /// when it was shrunk to fit
+    word_settings: Settings,
+    /// whether the rest of the current word is being dropped
+    truncated: bool,
+    /// words waiting to be broken into lines all at once
+    words: Vec<Word>,
+    /// index of the whitespace before `word`, if it started a new word
+    separator: Option<usize>,
+    /// index of the last whitespace read since the last word
+    space: Option<usize>,
+    in_word: bool,
+    ready: VecDeque<Text>,
+    done: bool,
+}
+
+impl<'a, I: Iterator<Item = (usize, char)>> Layout<'a, I> {
+    pub(crate) fn new(
+        chars: I,
+        font: &'a Font,
+        settings: &'a Settings,
+        max_width: Option<usize>,
+        options: Options,
+    ) -> Self {
+        Layout {
+            chars,
+            font,
+            faces: None,
+            settings,
+            max_width,
+            options,
+            grapheme: String::new(),
+            grapheme_index: 0,
+            line: Text::empty_with_capacity(font.height(), max_width.unwrap_or(usize::MAX)),
+            word: Text::empty_of_height(font.height()),
+            pieces: vec![],
+            word_settings: *settings,
+            truncated: false,
+            words: vec![],
+            separator: None,
+            space: None,
+            in_word: false,
+            ready: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Draws each character in its font from `faces`. The font given to
+    /// `new` should be their base font.
+    pub(crate) fn faces(mut self, faces: Option<&'a Faces>) -> Self {
+        self.faces = faces;
+        self
+    }
+
+    /// The font to draw the character at `index` in
+    fn font_at(&self, index: usize) -> &'a Font {
+        self.faces.map_or(self.font, |faces| faces.at(index))
+    }
+
+    fn push_char(&mut self, index: usize, c: char) {
+        if !self.grapheme.is_empty() {
+            // Only a line feed can extend a cluster of ASCII characters
+            let boundary = if c.is_ascii() && self.grapheme.is_ascii() {
+                !(c == '\n' && self.grapheme == "\r")
+            } else {
+                self.grapheme.push(c);
+                let boundary = self.grapheme.graphemes(true).nth(1).is_some();
+                self.grapheme.pop();
+                boundary
+            };
+            if boundary {
+                self.finish_grapheme();
+            }
+        }
+        if self.grapheme.is_empty() {
+            self.grapheme_index = index;
+        }
+        self.grapheme.push(c);
+        if c == '\n' {
+            // nothing extends a line feed, and the line it ends can be
+            // yielded without waiting for more input
+            self.finish_grapheme();
+        }
+    }
+
+    /// Lays out the grapheme cluster read so far
+    fn finish_grapheme(&mut self) {
+        let mut grapheme = std::mem::take(&mut self.grapheme);
+        self.push_grapheme(self.grapheme_index, &grapheme);
+        // Keep the buffer around for the next cluster
+        grapheme.clear();
+        self.grapheme = grapheme;
+    }
+
+    fn push_grapheme(&mut self, index: usize, grapheme: &str) {
+        let mut chars = grapheme.chars();
+        let Some(first) = chars.next() else {
+            return;
+        };
+        if grapheme == "\n" || grapheme == "\r\n" {
+            self.break_line();
+            return;
+        }
+        if first.is_whitespace() {
+            self.space = Some(index);
+            if self.in_word {
+                self.finish_word();
+                self.in_word = false;
+            }
+            return;
+        }
+
+        if !self.in_word {
+            self.in_word = true;
+            self.separator = self.space.take();
+        }
+        if self.truncated {
+            return;
+        }
+
+        let font = self.font_at(index);
+        let ch = if first.is_ascii() && chars.next().is_none() {
+            let c = if self.options.deutsch {
+                font::deutsch(first)
+            } else {
+                first
+            };
+            font.get_character(&c)
+        } else {
+            font.get_grapheme(grapheme)
+        };
+        self.push_glyph(index, ch);
+    }
+
+    /// The widest a line can be before it is scaled and pixelated
+    fn layout_width(&self) -> Option<usize> {
+        let mut width = self.max_width?;
+        if let Some(pixelate) = self.options.pixelate {
+            width = width.saturating_mul(pixelate.cell_size().0);
+        }
+        if let Some(scale) = self.options.scale {
+            width = scale.across.fitting(width);
+        }
+        Some(width)
+    }
+
+    /// Whether a line `width` columns wide would be too wide
+    fn too_wide(&self, width: usize) -> bool {
+        self.layout_width()
+            .is_some_and(|max_width| width > max_width)
+    }
+
+    fn push_glyph(&mut self, index: usize, ch: &'a Text) {
+        if !self.word.is_empty() && self.too_wide(self.word.line_width(&[ch], &self.word_settings))
+        {
+            // Too long for a line of its own
+            self.overflow(index, ch);
+        } else {
+            self.word.append_tagged(ch, index, &self.word_settings);
+            self.pieces.push((index, ch));
+        }
+    }
+
+    /// Lays out glyphs as a single word
+    fn build_word(&self, pieces: &[(usize, &Text)], settings: &Settings) -> Text {
+        let mut word = Text::empty_of_height(self.font.height());
+        for &(index, ch) in pieces {
+            word.append_tagged(ch, index, settings);
+        }
+        word
+    }
+
+    /// Lays out as many of the word's glyphs as fit on a line along
+    /// with `suffix`, which is left out if not even the first does.
+    /// Returns how many were used, always at least one.
+    fn fit_word(&mut self, suffix: &[&Text]) -> usize {
+        let settings = self.word_settings;
+        self.word.clear();
+        let mut used = 0;
+        for &(index, ch) in &self.pieces {
+            let mut parts = vec![ch];
+            parts.extend_from_slice(suffix);
+            if used > 0 && self.too_wide(self.word.line_width(&parts, &settings)) {
+                break;
+            }
+            self.word.append_tagged(ch, index, &settings);
+            used += 1;
+        }
+        if !self.too_wide(self.word.line_width(suffix, &settings)) {
+            for part in suffix {
+                self.word.append_in_place(part, &settings);
+            }
+        }
+        used
+    }
+
+    /// Deals with glyph `ch` not fitting on a line of its own after the
+    /// word being built, according to the overflow option
+    fn overflow(&mut self, index: usize, ch: &'a Text) {
+        match self.options.overflow {
+            Overflow::Break => {
+                self.finish_word();
+            }
+            Overflow::Hyphenate => {
+                let hyphen = self.font_at(index).get_character(&'-');
+                let used = self.fit_word(&[hyphen]);
+                let rest = self.pieces.split_off(used);
+                self.finish_word();
+                self.word = self.build_word(&rest, &self.word_settings);
+                self.pieces = rest;
+            }
+            Overflow::Shrink => {
+                self.pieces.push((index, ch));
+                for settings in tighter(&self.word_settings) {
+                    let word = self.build_word(&self.pieces, &settings);
+                    if !self.too_wide(word.line_width(&[], &settings)) {
+                        self.word = word;
+                        self.word_settings = settings;
+                        return;
+                    }
+                }
+                self.pieces.pop();
+                self.finish_word();
+            }
+            Overflow::Truncate => {
+                let dot = self.font_at(index).get_character(&'.');
+                self.pieces.push((index, ch));
+                self.fit_word(&[dot, dot, dot]);
+                self.truncated = true;
+                return;
+            }
+        }
+        self.push_glyph(index, ch);
+    }
+
+    /// Moves the word being built onto the current line, or onto a new
+    /// line if it doesn't fit. When wrapping for minimum raggedness, the
+    /// word is kept until the end of the input instead.
+    fn finish_word(&mut self) {
+        let separator = self.separator.take();
+        let space = self.font.get_character(&' ');
+
+        match self.options.wrap {
+            Wrap::Greedy => {
+                if !self.line.is_empty()
+                    && self.too_wide(self.line.line_width(&[space, &self.word], self.settings))
+                {
+                    self.finish_line();
+                }
+                append_word(&mut self.line, &self.word, separator, space, self.settings);
+                // Keep the word's buffers around for the next one
+                self.word.clear();
+            }
+            Wrap::MinimumRaggedness => {
+                let empty = Text::empty_of_height(self.font.height());
+                let text = std::mem::replace(&mut self.word, empty);
+                self.words.push(Word { separator, text });
+            }
+        }
+        self.pieces.clear();
+        self.word_settings = *self.settings;
+        self.truncated = false;
+    }
+
+    /// Ends the current line, and the paragraph it is in
+    fn break_line(&mut self) {
+        if self.in_word {
+            self.finish_word();
+            self.in_word = false;
+        }
+        self.space = None;
+        self.finish_paragraph();
+        self.finish_line();
+    }
+
+    /// Breaks the words kept for minimum raggedness into lines
+    fn finish_paragraph(&mut self) {
+        if self.words.is_empty() {
+            return;
+        }
+        let words = std::mem::take(&mut self.words);
+        let space = self.font.get_character(&' ');
+        let lines = wrap::minimum_raggedness(
+            &words,
+            space,
+            self.font.height(),
+            self.settings,
+            self.layout_width(),
+        );
+        for line in lines {
+            self.line = line;
+            self.finish_line();
+        }
+    }
+
+    fn finish_line(&mut self) {
+        let capacity = self.layout_width().unwrap_or(usize::MAX);
+        let empty = Text::empty_with_capacity(self.font.height(), capacity);
+        let mut line = std::mem::replace(&mut self.line, empty);
+        if !line.is_empty() {
+            line.justify(self.settings, self.layout_width());
+            if let Some(scale) = self.options.scale {
+                line = line.scale(scale, self.settings.hardblank);
+            }
+            if let Some(pixelate) = self.options.pixelate {
+                line = line.pixelate(pixelate, self.settings.hardblank);
+            }
+            self.ready.push_back(line);
+        }
+    }
+}
+
+/// Settings that lay words out tighter than `settings`, from loosest
+/// to tightest: kerning, then smushing by the font's rules, or by
+/// universal overlapping if it has none
+fn tighter(settings: &Settings) -> Vec<Settings> {
+    let mut modes = vec![];
+    if settings.is_smush() {
+        return modes;
+    }
+    if !settings.smushmode.contains(SmushMode::KERN) {
+        modes.push(Settings {
+            smushmode: SmushMode::KERN,
+            ..*settings
+        });
+    }
+    let rules = settings.smushmode & SmushMode::from_bits_truncate(63);
+    modes.push(Settings {
+        smushmode: rules | SmushMode::SMUSH,
+        ..*settings
+    });
+    modes
+}
+
+impl<I: Iterator<Item = (usize, char)>> Iterator for Layout<'_, I> {
+    type Item = Text;
+
+    fn next(&mut self) -> Option<Text> {
+        while self.ready.is_empty() && !self.done {
+            match self.chars.next() {
+                Some((index, c)) => self.push_char(index, c),
+                None => {
+                    self.finish_grapheme();
+                    self.break_line();
+                    self.done = true;
+                }
+            }
+        }
+        self.ready.pop_front()
+    }
+}
+
+/// Numbers the characters of a stream, as `Enumerate` would, keeping
+/// those read since the last art line so that the next one can be
+/// given the input it was drawn from
+struct Recorded<I> {
+    chars: I,
+    /// the characters read and not yet handed to an art line
+    kept: VecDeque<char>,
+    /// the index of the first of `kept`
+    start: usize,
+}
+
+impl<I: Iterator<Item = char>> Iterator for Recorded<I> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let c = self.chars.next()?;
+        self.kept.push_back(c);
+        Some((self.start + self.kept.len() - 1, c))
+    }
+}
+
+impl<I> Recorded<I> {
+    /// Hands the input `line` was drawn from to it, numbering its spans
+    /// from the start of that, and returns it. What was read before it
+    /// is forgotten.
+    fn take_source(&mut self, line: &mut Text) -> String {
+        let Some(first) = line.spans.iter().map(|span| span.index).min() else {
+            return line.text.clone();
+        };
+        let last = line
+            .spans
+            .iter()
+            .map(|span| span.index)
+            .max()
+            .unwrap_or(first);
+        let skip = first.saturating_sub(self.start).min(self.kept.len());
+        self.kept.drain(..skip);
+        self.start += skip;
+        // up to the end of the grapheme cluster the last span is of
+        let rest: String = self
+            .kept
+            .iter()
+            .skip(last.saturating_sub(self.start))
+            .collect();
+        let end = last + rest.graphemes(true).next().map_or(1, |g| g.chars().count());
+        let taken = (end - self.start).min(self.kept.len());
+        let source = self.kept.drain(..taken).collect();
+        for span in line.spans.iter_mut() {
+            span.index -= first;
+        }
+        self.start += taken;
+        source
+    }
+}
+
+/// Renders a stream of characters lazily, yielding each art line as
+/// soon as it is complete. Only the line and word currently being laid
+/// out are kept in memory, so arbitrarily long input can be piped
+/// through in linear time.
+pub struct ArtStream<'a, I> {
+    layout: Layout<'a, Recorded<I>>,
+}
+
+impl<'a, I: Iterator<Item = char>> ArtStream<'a, I> {
+    /// `max_width` is the width to wrap lines at, or `None` to never
+    /// wrap. A plain `usize` will do for the former.
+    pub fn new(
+        chars: I,
+        font: &'a Font,
+        settings: &'a Settings,
+        max_width: impl Into<Option<usize>>,
+    ) -> Self {
+        ArtStream {
+            layout: Layout::new(
+                Recorded {
+                    chars,
+                    kept: VecDeque::new(),
+                    start: 0,
+                },
+                font,
+                settings,
+                max_width.into(),
+                Options::default(),
+            ),
+        }
+    }
+
+    /// Deutsch mode, like figlet's -D. See `ArtOutput::deutsch`.
+    pub fn deutsch(mut self, deutsch: bool) -> Self {
+        self.layout.options.deutsch = deutsch;
+        self
+    }
+
+    /// What to do with words too wide for a line. See `Overflow`.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.layout.options.overflow = overflow;
+        self
+    }
+
+    /// How to break lines. With `Wrap::MinimumRaggedness` nothing is
+    /// yielded until the input ends.
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.layout.options.wrap = wrap;
+        self
+    }
+
+    /// Draws each line bigger or smaller. See `ArtOutput::scale`.
+    pub fn scale(mut self, scale: impl Into<Option<Scale>>) -> Self {
+        self.layout.options.scale = scale.into();
+        self
+    }
+
+    /// Redraws each line in terminal graphics. See `ArtOutput::pixelate`.
+    pub fn pixelate(mut self, pixelate: impl Into<Option<Pixelate>>) -> Self {
+        self.layout.options.pixelate = pixelate.into();
+        self
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for ArtStream<'_, I> {
+    type Item = Rendered;
+
+    /// Returns the next art line, rendered on its own. Its source is the
+    /// part of the input it was drawn from, from its first character to
+    /// its last, and its spans index into that rather than the whole
+    /// input.
+    fn next(&mut self) -> Option<Rendered> {
+        let hardblank = self.layout.font.hardblank();
+        let mut line = self.layout.next()?;
+        let source = self.layout.chars.take_source(&mut line);
+        Some(Rendered::from_texts(vec![line], hardblank, &source))
+    }
+}
+
+/// Given a stream of characters, font, settings, and maximum width
+/// (`None` for no limit), lazily formats the characters using the font.
+/// See `ArtStream`.
+pub fn art_stream<'a, I: IntoIterator<Item = char>>(
+    chars: I,
+    font: &'a Font,
+    settings: &'a Settings,
+    max_width: impl Into<Option<usize>>,
+) -> ArtStream<'a, I::IntoIter> {
+    ArtStream::new(chars.into_iter(), font, settings, max_width)
+}
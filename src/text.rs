@@ -1,30 +1,152 @@
+use crate::bidi;
 use crate::font::Font;
+#[cfg(test)]
+use crate::grid::CONTINUATION;
+use crate::grid::{is_wide, Row};
+use crate::mix::{Faces, Mix};
+use crate::pixelate::Pixelate;
+use crate::render::Rendered;
+use crate::scale::Scale;
 use crate::settings::{Settings, SmushMode};
+use crate::stream::{Layout, Options, Overflow};
+use crate::wrap::Wrap;
 use std::cmp::min;
-use std::collections::VecDeque;
 use std::fmt;
+use std::iter;
+use std::ops::Range;
+
+pub type Art = Vec<Row>;
+
+/// The widest line right-to-left text is pushed against the right
+/// margin of, as wide as any terminal. Past that, lines are left as
+/// they are rather than padded with more blanks than could be shown.
+pub const MAX_MARGIN: usize = u16::MAX as usize;
+
+/// The columns of a `Text` that were produced by a single input
+/// character.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// index of the originating character in the input, counted in chars
+    pub index: usize,
+    /// the columns occupied by the character's art. Spans of
+    /// neighbouring characters overlap where they were smushed together.
+    pub columns: Range<usize>,
+}
+
+impl Span {
+    pub fn contains(&self, column: usize) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// The columns shared with `other`, if the two were smushed together
+    pub fn overlap(&self, other: &Span) -> Option<Range<usize>> {
+        let start = self.columns.start.max(other.columns.start);
+        let end = self.columns.end.min(other.columns.end);
+        if start < end {
+            Some(start..end)
+        } else {
+            None
+        }
+    }
+
+    fn shift(&mut self, by: usize) {
+        self.columns = self.columns.start + by..self.columns.end + by;
+    }
 
-type Art = Vec<VecDeque<char>>;
+    fn shift_back(&mut self, by: usize) {
+        self.columns = self.columns.start.saturating_sub(by)..self.columns.end.saturating_sub(by);
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
     /// the artwork, as lines
     pub art: Art,
     /// the unadorned text
     pub text: String,
+    /// where each input character ended up, ordered left to right
+    pub spans: Vec<Span>,
+    /// how many rows down the baseline is, counting it: the rows above
+    /// and on it are the ascent, the rest hang below it
+    pub baseline: usize,
 }
 
-/// Given 2 characters, attempts to smush them into 1, according to
-/// smushmode.  Returns smushed character or '\0' if no smushing can be
-/// done.
+/// How to line up art of different heights side by side
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// along their top rows
+    Top,
+    /// centred, with any odd row left below
+    Middle,
+    /// on their baselines, as the characters of a font sit
+    #[default]
+    Baseline,
+}
+
+/// The rows that pieces of art of different heights take up once lined
+/// up: the tallest of them, and the tallest ascent and deepest descent
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Frame {
+    height: usize,
+    ascent: usize,
+    descent: usize,
+}
+
+impl Frame {
+    /// The frame that fits art of each height and baseline given
+    pub(crate) fn of(extents: impl IntoIterator<Item = (usize, usize)>) -> Frame {
+        extents
+            .into_iter()
+            .fold(Frame::default(), |frame, (height, baseline)| {
+                let baseline = baseline.min(height);
+                Frame {
+                    height: frame.height.max(height),
+                    ascent: frame.ascent.max(baseline),
+                    descent: frame.descent.max(height - baseline),
+                }
+            })
+    }
+
+    /// How many blank rows to put above and below art `height` rows high,
+    /// with its baseline `baseline` rows down, to line it up in the frame
+    pub(crate) fn padding(&self, height: usize, baseline: usize, align: Align) -> (usize, usize) {
+        let baseline = baseline.min(height);
+        let (above, total) = match align {
+            Align::Top => (0, self.height),
+            Align::Middle => ((self.height - height) / 2, self.height),
+            Align::Baseline => (self.ascent - baseline, self.ascent + self.descent),
+        };
+        (above, total - height - above)
+    }
+}
 
+/// Position of `ch` in the smushing hierarchy "|", "/\\", "[]", "{}",
+/// "()", "<>", from low to high precedence.
+fn hierarchy_class(ch: char) -> Option<usize> {
+    match ch {
+        '|' => Some(0),
+        '/' | '\\' => Some(1),
+        '[' | ']' => Some(2),
+        '{' | '}' => Some(3),
+        '(' | ')' => Some(4),
+        '<' | '>' => Some(5),
+        _ => None,
+    }
+}
+
+/// Given 2 characters, attempts to smush them into 1, according to
+/// smushmode. Returns the smushed character, or `None` if they can't be
+/// smushed.
+//
 // smushmode values are sum of following (all values smush blanks):
 // 1: Smush equal chars (not hardblanks)
 // 2: Smush '_' with any char in hierarchy below
 // 4: hierarchy: "|", "/\", "[]", "{}", "()", "<>"
 //    Each class in hier. can be replaced by later class.
 // 8: [ + ] -> |, { + } -> |, ( + ) -> |
-// 16: / + \ -> X, > + < -> X (only in that order)
+// 16: / + \ -> |, \ + / -> Y, > + < -> X (only in those orders)
 // 32: hardblank + hardblank -> hardblank
 fn smushem(lch: char, rch: char, settings: &Settings) -> Option<char> {
     if lch == ' ' {
@@ -48,6 +170,11 @@ fn smushem(lch: char, rch: char, settings: &Settings) -> Option<char> {
             return Some(lch);
         }
 
+        // wide sub-characters can't be split
+        if is_wide(lch) || is_wide(rch) {
+            return None;
+        }
+
         // ensure dominant char overlaps, depending on right-to-left parameter
         if settings.right2left {
             return Some(lch);
@@ -66,6 +193,10 @@ fn smushem(lch: char, rch: char, settings: &Settings) -> Option<char> {
         return None;
     }
 
+    if is_wide(lch) || is_wide(rch) {
+        return None;
+    }
+
     if settings.smushmode.intersects(SmushMode::EQUAL) && lch == rch {
         return Some(lch);
     }
@@ -80,22 +211,18 @@ fn smushem(lch: char, rch: char, settings: &Settings) -> Option<char> {
     }
 
     if settings.smushmode.intersects(SmushMode::HIERARCHY) {
-        let hierarchy = ["|", "/\\", "[]", "{}", "()", "<>"]; // low -> high precedence
-
-        for i in 0..hierarchy.len() {
-            let c = hierarchy[i];
-            let rest = hierarchy[i + 1..].join("");
-            if c.contains(lch) && rest.contains(rch) {
+        if let (Some(l), Some(r)) = (hierarchy_class(lch), hierarchy_class(rch)) {
+            if l < r {
                 return Some(rch);
             }
-            if c.contains(rch) && rest.contains(lch) {
+            if r < l {
                 return Some(lch);
             }
         }
     }
 
     if settings.smushmode.intersects(SmushMode::PAIR) {
-        let pairs = vec![['[', ']'], ['{', '}'], ['(', ')']];
+        let pairs = [['[', ']'], ['{', '}'], ['(', ')']];
         for pair in pairs {
             let [open, close] = pair;
             if (rch == open && lch == close) || (rch == close && lch == open) {
@@ -118,6 +245,23 @@ fn smushem(lch: char, rch: char, settings: &Settings) -> Option<char> {
     None
 }
 
+/// Merges `right` onto the end of `row`, overlapping the last `amount`
+/// columns.
+fn smush_onto(row: &mut Row, right: &[char], amount: usize, settings: &Settings) {
+    let rowlen = row.len();
+    for (k, &rch) in right.iter().enumerate().take(amount) {
+        let column = (rowlen + k).saturating_sub(amount);
+        if column >= row.len() {
+            row.push(rch);
+            continue;
+        }
+        if let Some(smushed) = smushem(row[column], rch, settings) {
+            row[column] = smushed;
+        }
+    }
+    row.extend(right.iter().skip(amount))
+}
+
 impl Text {
     fn calculate_smush_amount(&self, other: &Text, settings: &Settings) -> usize {
         let s = settings.smushmode;
@@ -128,24 +272,38 @@ impl Text {
         let other_len = other.width();
 
         // For each row of the artwork...
-        let answer = (0..self.height())
-            .map(|row| {
+        let answer = self
+            .art
+            .iter()
+            .zip(other.art.iter())
+            .map(|(this, that)| {
                 let (left, right) = if settings.right2left {
-                    (&other.art[row], &self.art[row])
+                    (that, this)
                 } else {
-                    (&self.art[row], &other.art[row])
+                    (this, that)
                 };
-                let l_blanks = left.iter().rev().take_while(|&c| *c == ' ').count();
-                let r_blanks = right.iter().take_while(|&c| *c == ' ').count();
+                // Blanks beyond the width of `other` can't change the answer,
+                // so don't scan long lines all the way through
+                let l_blanks = left
+                    .iter()
+                    .rev()
+                    .take(other_len)
+                    .take_while(|&c| *c == ' ')
+                    .count();
+                let r_blanks = right
+                    .iter()
+                    .take(other_len)
+                    .take_while(|&c| *c == ' ')
+                    .count();
                 let mut rowsmush = l_blanks + r_blanks;
                 let ch1 = left.iter().rev().nth(l_blanks);
                 let ch2 = right.get(r_blanks);
                 match (ch1, ch2) {
                     (None, _) | (Some(' '), _) => rowsmush += 1,
-                    (Some(&c1), Some(&c2)) => {
-                        if right.len() > rowsmush && smushem(c1, c2, settings).is_some() {
-                            rowsmush += 1
-                        }
+                    (Some(&c1), Some(&c2))
+                        if right.len() > rowsmush && smushem(c1, c2, settings).is_some() =>
+                    {
+                        rowsmush += 1
                     }
                     _ => (),
                 }
@@ -160,99 +318,291 @@ impl Text {
         }
     }
 
+    /// This text with `other` appended, lined up on their baselines if
+    /// their heights or baselines differ
     pub fn append(&self, other: &Text, settings: &Settings) -> Text {
+        let mut result = self.clone();
+        result.append_in_place(other, settings);
+        result
+    }
+
+    /// Appends `other` to this text without copying what is already
+    /// there. Only the columns that get smushed are touched, so building
+    /// up a line one character at a time is linear in its width.
+    pub fn append_in_place(&mut self, other: &Text, settings: &Settings) {
+        self.append_with_spans(other, &other.spans, settings)
+    }
+
+    /// Appends the art of `other`, recording that it was produced by the
+    /// input character at `index`.
+    pub fn append_tagged(&mut self, other: &Text, index: usize, settings: &Settings) {
+        let span = Span {
+            index,
+            columns: 0..other.width(),
+        };
+        self.append_with_spans(other, &[span], settings)
+    }
+
+    /// Appends `other`, lining the two up as `align` says if their
+    /// heights or baselines differ. The other ways of appending line
+    /// them up on their baselines.
+    pub fn append_aligned(&mut self, other: &Text, align: Align, settings: &Settings) {
+        let padded = self.line_up(other, align);
+        let other = padded.as_ref().unwrap_or(other);
+        self.join(other, &other.spans, settings)
+    }
+
+    fn append_with_spans(&mut self, other: &Text, spans: &[Span], settings: &Settings) {
+        let padded = self.line_up(other, Align::Baseline);
+        self.join(padded.as_ref().unwrap_or(other), spans, settings)
+    }
+
+    /// Pads this text with blank rows, and returns a padded copy of
+    /// `other` if that needs them too, so that the two line up as
+    /// `align` says. An empty text takes on the height and baseline of
+    /// `other` instead.
+    fn line_up(&mut self, other: &Text, align: Align) -> Option<Text> {
+        if self.is_empty() {
+            self.art.resize_with(other.height(), Row::new);
+            self.baseline = other.baseline;
+            return None;
+        }
+        if self.lines_up_with(other, align) {
+            return None;
+        }
+        let frame = Frame::of([self.extent(), other.extent()]);
+        let (above, below) = frame.padding(self.height(), self.baseline, align);
+        self.pad_rows_in_place(above, below);
+        let (above, below) = frame.padding(other.height(), other.baseline, align);
+        Some(other.pad_rows(above, below))
+    }
+
+    /// Whether `other` can be appended as it is
+    fn lines_up_with(&self, other: &Text, align: Align) -> bool {
+        self.height() == other.height()
+            && (align != Align::Baseline || self.baseline == other.baseline)
+    }
+
+    /// Appends `other`, which has as many rows, row by row
+    fn join(&mut self, other: &Text, spans: &[Span], settings: &Settings) {
         let smushamount = self.calculate_smush_amount(other, settings);
         // println!("append {} <- {} smush: {}", self.text, other.text, smushamount);
-        let left;
-        let right;
         if settings.right2left {
-            left = other;
-            right = self;
+            let offset = other.width().saturating_sub(smushamount);
+            let mut merged = Row::new();
+            let mut head = vec![];
+            for (row, left) in self.art.iter_mut().zip(other.art.iter()) {
+                head.clear();
+                row.take_front(smushamount, &mut head);
+                merged.clear();
+                merged.extend(left.iter());
+                smush_onto(&mut merged, &head, smushamount, settings);
+                row.prepend(&merged);
+            }
+            self.text.insert_str(0, &other.text);
+            for span in self.spans.iter_mut() {
+                span.shift(offset);
+            }
+            self.spans.splice(0..0, spans.iter().cloned());
         } else {
-            left = self;
-            right = other;
+            let offset = self.width().saturating_sub(smushamount);
+            for (row, right) in self.art.iter_mut().zip(other.art.iter()) {
+                smush_onto(row, right, smushamount, settings);
+            }
+            self.text.push_str(&other.text);
+            self.spans.extend(spans.iter().cloned().map(|mut span| {
+                span.shift(offset);
+                span
+            }));
         }
+    }
 
-        let mut result = left.art.clone();
-        let mut text = left.text.clone();
-        text.push_str(right.text.as_str());
-
-        for (i, item) in result.iter_mut().enumerate().take(self.height()) {
-            let resultlen = item.len();
-            for k in 0..smushamount {
-                let kcol = resultlen + k;
-                let column = if kcol < smushamount {
-                    0
-                } else {
-                    kcol - smushamount
-                };
-                let rch = right.art[i][k];
+    /// The width this text would have after appending each of `parts`
+    /// in turn. Smushing only ever reaches as far into the existing art
+    /// as the width of what is being appended, so at most that edge is
+    /// copied and measured.
+    pub fn appended_width(&self, parts: &[&Text], settings: &Settings) -> usize {
+        if let [other] = parts {
+            if self.is_empty() || self.lines_up_with(other, Align::Baseline) {
+                return self.appended_width_of(other, settings);
+            }
+        }
+        let reach: usize = parts.iter().map(|part| part.width()).sum();
+        let mut edge = self.edge(reach, settings);
+        let untouched = self.width() - edge.width();
+        for part in parts {
+            edge.append_in_place(part, settings);
+        }
+        untouched + edge.width()
+    }
 
-                if column >= item.len() {
-                    item.push_back(rch);
-                    continue;
-                }
-                let lch = item[column];
+    /// The width of the first row after appending `other`, worked out
+    /// the same way `smush_onto` builds it, without building it.
+    fn appended_width_of(&self, other: &Text, settings: &Settings) -> usize {
+        let amount = self.calculate_smush_amount(other, settings);
+        let (Some(row), Some(other_row)) = (self.art.first(), other.art.first()) else {
+            return 0;
+        };
+        // In right-to-left mode, the head of this row is smushed onto the
+        // end of `other`'s row instead
+        let (left, right, rest) = if settings.right2left {
+            let head = amount.min(row.len());
+            (other_row.len(), head, row.len() - head)
+        } else {
+            (row.len(), other_row.len(), 0)
+        };
+        // Overlapping an empty row still places one sub-character
+        let placed = if left == 0 && amount > 0 && right > 0 {
+            1
+        } else {
+            left
+        };
+        placed + right.saturating_sub(amount) + rest
+    }
 
-                if let Some(smushed) = smushem(lch, rch, settings) {
-                    item[column] = smushed;
+    /// Copies the outermost `columns` columns on the side that new
+    /// characters are appended to.
+    fn edge(&self, columns: usize, settings: &Settings) -> Text {
+        let art = self
+            .art
+            .iter()
+            .map(|row| {
+                let n = columns.min(row.len());
+                if settings.right2left {
+                    row[..n].iter().copied().collect()
+                } else {
+                    row[row.len() - n..].iter().copied().collect()
                 }
-            }
-            item.extend(right.art[i].iter().skip(smushamount))
+            })
+            .collect();
+        Text {
+            art,
+            text: String::new(),
+            spans: vec![],
+            baseline: self.baseline,
         }
-        Text { art: result, text }
     }
 
     pub fn width(&self) -> usize {
-        self.art[0].len()
+        self.art.first().map_or(0, |row| row.len())
     }
 
     pub fn height(&self) -> usize {
         self.art.len()
     }
 
+    /// The height, and how many rows down the baseline is
+    pub(crate) fn extent(&self) -> (usize, usize) {
+        (self.height(), self.baseline)
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.art.is_empty() || self.art[0].is_empty()
+        self.width() == 0
     }
 
+    /// An empty text with its baseline at the bottom
     pub fn empty_of_height(height: u32) -> Self {
-        let art: Art = (0..height).map(|_| VecDeque::new()).collect();
+        let art: Art = (0..height).map(|_| Row::new()).collect();
         Text {
             text: String::from(""),
             art,
+            spans: vec![],
+            baseline: height as usize,
+        }
+    }
+
+    /// An empty text whose rows can grow to `width` columns without
+    /// reallocating
+    pub fn empty_with_capacity(height: u32, width: usize) -> Self {
+        // Avoid reserving silly amounts for very wide (or unlimited) lines
+        let width = width.min(1024);
+        let art: Art = (0..height).map(|_| Row::with_capacity(width)).collect();
+        Text {
+            text: String::new(),
+            art,
+            spans: vec![],
+            baseline: height as usize,
+        }
+    }
+
+    /// This art with `above` blank rows over it and `below` under it,
+    /// as wide as it is
+    pub fn pad_rows(&self, above: usize, below: usize) -> Text {
+        let mut text = self.clone();
+        text.pad_rows_in_place(above, below);
+        text
+    }
+
+    fn pad_rows_in_place(&mut self, above: usize, below: usize) {
+        let width = self.width();
+        let blank = || Row::from(vec![' '; width]);
+        self.art.splice(0..0, iter::repeat_with(blank).take(above));
+        self.art.extend(iter::repeat_with(blank).take(below));
+        self.baseline += above;
+    }
+
+    /// Empties the text, keeping its height and allocated buffers
+    pub fn clear(&mut self) {
+        for row in self.art.iter_mut() {
+            row.clear();
         }
+        self.text.clear();
+        self.spans.clear();
     }
 
-    pub fn justify(&mut self, settings: &Settings, width: usize) {
+    /// Aligns the line for output: right-to-left lines are pushed
+    /// against the right margin of a `width` column line, if there is
+    /// one no wider than `MAX_MARGIN`, and left-to-right lines lose any
+    /// blank column at the front.
+    pub fn justify(&mut self, settings: &Settings, width: impl Into<Option<usize>>) {
         if settings.right2left {
+            let width = width.into().filter(|&width| width <= MAX_MARGIN).unwrap_or(0);
             if self.width() < width {
-                let pad = width - self.width() - 1;
+                let pad = vec![' '; width - self.width() - 1];
                 for line in self.art.iter_mut() {
-                    for _ in 0..pad {
-                        line.push_front(' ');
-                    }
+                    line.prepend(&pad);
                 }
-            }
-        } else if settings.can_trim_line() {
-            let front_spaces = self
-                .art
-                .iter()
-                .map(|line| line.iter().take_while(|&&c| c == ' ').count())
-                .min()
-                .unwrap_or(0);
-            if front_spaces > 0 {
-                for line in self.art.iter_mut() {
-                    line.remove(0);
+                for span in self.spans.iter_mut() {
+                    span.shift(pad.len());
                 }
             }
+        } else if self.trimmed_columns(settings) > 0 {
+            for line in self.art.iter_mut() {
+                line.remove_front(1);
+            }
+            for span in self.spans.iter_mut() {
+                span.shift_back(1);
+            }
         }
     }
+
+    /// The number of columns `justify` removes from the front
+    fn trimmed_columns(&self, settings: &Settings) -> usize {
+        let trimmable = !settings.right2left
+            && settings.can_trim_line()
+            && !self.art.is_empty()
+            && self.art.iter().all(|line| line.first() == Some(&' '));
+        usize::from(trimmable)
+    }
+
+    /// The width of the line this text would make, once justified,
+    /// after appending each of `parts` in turn. Unlike `appended_width`
+    /// this counts only the columns that end up in the output, so it
+    /// is the exact measure to wrap lines by.
+    pub fn line_width(&self, parts: &[&Text], settings: &Settings) -> usize {
+        // Appending never changes the front of a left-to-right line
+        let trimmed = match parts.first() {
+            Some(first) if self.is_empty() => first.trimmed_columns(settings),
+            _ => self.trimmed_columns(settings),
+        };
+        self.appended_width(parts, settings) - trimmed
+    }
 }
 
 impl fmt::Display for Text {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for line in &self.art {
-            for &ch in line.iter() {
+            for ch in line.display() {
                 write!(f, "{}", ch)?;
             }
             writeln!(f)?;
@@ -265,86 +615,393 @@ pub struct ArtOutput<'a> {
     input: &'a str,
     font: &'a Font,
     settings: &'a Settings,
-    max_width: usize,
+    max_width: Option<usize>,
+    options: Options,
+    mix: Option<&'a Mix<'a>>,
 }
 
 impl<'a> ArtOutput<'a> {
+    /// `max_width` is the width to wrap lines at, or `None` to never
+    /// wrap. A plain `usize` will do for the former.
     pub fn build(
         message: &'a str,
         font: &'a Font,
         settings: &'a Settings,
-        max_width: usize,
+        max_width: impl Into<Option<usize>>,
     ) -> Self {
         ArtOutput {
             input: message,
             font,
             settings,
-            max_width,
+            max_width: max_width.into(),
+            options: Options::default(),
+            mix: None,
         }
     }
 
-    pub fn lines(&self) -> Vec<String> {
-        let space = self.font.get_character(&' ');
-        let words: Vec<Text> = self
-            .input
-            .split_whitespace()
-            .flat_map(|word| {
-                let mut result = vec![];
-                let mut line = Text::empty_of_height(self.font.height());
-                for ch in word.chars().map(|c| self.font.get_character(&c)) {
-                    let new_line = line.append(ch, self.settings);
-                    if !line.is_empty() && new_line.width() > self.max_width {
-                        result.push(line);
-                        line = Text::empty_of_height(self.font.height()).append(ch, self.settings);
-                    } else {
-                        line = new_line
-                    }
-                }
-                if !line.is_empty() {
-                    result.push(line);
-                }
-                result.into_iter()
-            })
-            .collect();
+    /// Lays out a message in several fonts. `settings` would usually
+    /// come from `Mix::settings`.
+    pub fn mixed(
+        mix: &'a Mix<'a>,
+        settings: &'a Settings,
+        max_width: impl Into<Option<usize>>,
+    ) -> Self {
+        ArtOutput {
+            mix: Some(mix),
+            ..ArtOutput::build(mix.text(), mix.base(), settings, max_width)
+        }
+    }
 
-        let mut result = vec![];
-        let mut line = Text::empty_of_height(self.font.height());
+    /// Deutsch mode, like figlet's -D: draws `[\]{|}~` as the German
+    /// characters Ä Ö Ü ä ö ü ß. See `font::DEUTSCH_MAPPING`.
+    pub fn deutsch(mut self, deutsch: bool) -> Self {
+        self.options.deutsch = deutsch;
+        self
+    }
 
-        for word in words {
-            if line.is_empty() {
-                line = line.append(&word, self.settings);
-            } else {
-                let new_line = line
-                    .append(space, self.settings)
-                    .append(&word, self.settings);
-                if new_line.width() > self.max_width {
-                    result.push(line);
-                    line = Text::empty_of_height(self.font.height()).append(&word, self.settings);
-                } else {
-                    line = new_line;
-                }
+    /// What to do with words too wide for a line. See `Overflow`.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.options.overflow = overflow;
+        self
+    }
+
+    /// How to break lines between words. See `Wrap`.
+    pub fn wrap(mut self, wrap: Wrap) -> Self {
+        self.options.wrap = wrap;
+        self
+    }
+
+    /// Bidi-aware layout: reorders each paragraph of the message with
+    /// the Unicode Bidirectional Algorithm, and lays it out in its own
+    /// base direction, so right-to-left text mixed with left-to-right
+    /// text reads correctly. The font's print direction is ignored.
+    /// Each paragraph (ended by a newline) starts a new art line, and
+    /// is reordered as a whole before being broken into art lines.
+    pub fn bidi(mut self, bidi: bool) -> Self {
+        self.options.bidi = bidi;
+        self
+    }
+
+    /// Draws the art bigger or smaller, or as it is if `None`. See
+    /// `Scale`. Lines are still wrapped at `max_width` columns of output.
+    /// Scaling comes before pixelation.
+    pub fn scale(mut self, scale: impl Into<Option<Scale>>) -> Self {
+        self.options.scale = scale.into();
+        self
+    }
+
+    /// Redraws the art in terminal graphics, or not if `None`. See
+    /// `Pixelate`. Lines are still wrapped at `max_width` columns of
+    /// output, so they can hold more of the font's sub-characters.
+    pub fn pixelate(mut self, pixelate: impl Into<Option<Pixelate>>) -> Self {
+        self.options.pixelate = pixelate.into();
+        self
+    }
+
+    /// Lays out the message, returning the structured result
+    pub fn render(&self) -> Rendered {
+        let faces = self.mix.map(Mix::faces);
+        let faces = faces.as_ref();
+        let font = faces.map_or(self.font, Faces::base);
+        let mut lines: Vec<Text> = vec![];
+        if self.options.bidi {
+            for paragraph in bidi::paragraphs(self.input) {
+                let settings = Settings {
+                    right2left: paragraph.rtl,
+                    ..*self.settings
+                };
+                lines.extend(
+                    Layout::new(
+                        paragraph.chars.into_iter(),
+                        font,
+                        &settings,
+                        self.max_width,
+                        self.options,
+                    )
+                    .faces(faces),
+                );
             }
+        } else {
+            lines.extend(
+                Layout::new(
+                    self.input.chars().enumerate(),
+                    font,
+                    self.settings,
+                    self.max_width,
+                    self.options,
+                )
+                .faces(faces),
+            );
         }
+        Rendered::from_texts(lines, font.hardblank(), self.input)
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.render().to_lines()
+    }
+}
+
+/// Given a message, font, settings, and maximum width (`None` for no
+/// limit), formats the message using the font. Returns "art lines",
+/// that is, a Vec<String> that, when printed sequentially, will
+/// resemble "lines" of text. Each String can be multiple lines.
+pub fn art_lines(
+    message: &str,
+    font: &Font,
+    settings: &Settings,
+    max_width: impl Into<Option<usize>>,
+) -> Vec<String> {
+    ArtOutput::build(message, font, settings, max_width).lines()
+}
+
+/// Given a message, font, settings, and maximum width (`None` for no
+/// limit), formats the message using the font, keeping the full grid
+/// of cells along with the boundaries of each art line. See `Rendered`.
+pub fn render(
+    message: &str,
+    font: &Font,
+    settings: &Settings,
+    max_width: impl Into<Option<usize>>,
+) -> Rendered {
+    ArtOutput::build(message, font, settings, max_width).render()
+}
 
-        if !line.is_empty() {
-            result.push(line);
+#[cfg(test)]
+fn smush_settings(smushmode: SmushMode, right2left: bool) -> Settings {
+    Settings {
+        hardblank: '$',
+        charheight: 1,
+        right2left,
+        smushmode,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+impl Text {
+    /// Art of `rows` as they would be displayed, for tests, with no
+    /// source text or spans
+    pub(crate) fn from_rows(rows: &[&str]) -> Text {
+        Text {
+            art: rows
+                .iter()
+                .map(|row| Row::from_display(row.chars()))
+                .collect(),
+            text: String::new(),
+            spans: vec![],
+            baseline: rows.len(),
         }
+    }
+
+    /// The rows of the art as they are displayed, for tests
+    pub(crate) fn display_rows(&self) -> Vec<String> {
+        self.art.iter().map(|row| row.display().collect()).collect()
+    }
+}
+
+#[cfg(test)]
+fn glyph(rows: &[Vec<char>]) -> Text {
+    Text {
+        art: rows.iter().map(|row| Row::from(row.clone())).collect(),
+        text: String::new(),
+        spans: vec![],
+        baseline: rows.len(),
+    }
+}
 
-        for text in result.iter_mut() {
-            text.justify(self.settings, self.max_width);
+/// Examples of each horizontal smushing rule from figfont.txt, with
+/// what each rule on its own makes of the pair: EQUAL, LOWLINE,
+/// HIERARCHY, PAIR, BIGX and HARDBLANK, in that order.
+#[cfg(test)]
+#[rustfmt::skip]
+const RULE_EXAMPLES: [(char, char, [Option<char>; 6]); 17] = [
+    ('|', '|',  [Some('|'), None,      None,      None,      None,      None]),
+    ('_', '_',  [Some('_'), None,      None,      None,      None,      None]),
+    ('#', '#',  [Some('#'), None,      None,      None,      None,      None]),
+    ('_', '|',  [None,      Some('|'), None,      None,      None,      None]),
+    ('/', '_',  [None,      Some('/'), None,      None,      None,      None]),
+    ('_', '<',  [None,      Some('<'), None,      None,      None,      None]),
+    ('|', '/',  [None,      None,      Some('/'), None,      None,      None]),
+    ('{', '(',  [None,      None,      Some('('), None,      None,      None]),
+    ('>', '}',  [None,      None,      Some('>'), None,      None,      None]),
+    ('[', ']',  [None,      None,      None,      Some('|'), None,      None]),
+    (')', '(',  [None,      None,      None,      Some('|'), None,      None]),
+    ('/', '\\', [None,      None,      None,      None,      Some('|'), None]),
+    ('\\', '/', [None,      None,      None,      None,      Some('Y'), None]),
+    ('>', '<',  [None,      None,      None,      None,      Some('X'), None]),
+    ('<', '>',  [None,      None,      None,      None,      None,      None]),
+    ('$', '$',  [None,      None,      None,      None,      None,      Some('$')]),
+    ('$', '|',  [None,      None,      None,      None,      None,      None]),
+];
+
+#[test]
+fn smushem_rule_combinations() {
+    let rules = [
+        SmushMode::EQUAL,
+        SmushMode::LOWLINE,
+        SmushMode::HIERARCHY,
+        SmushMode::PAIR,
+        SmushMode::BIGX,
+        SmushMode::HARDBLANK,
+    ];
+    // Every non-empty combination of rules: the first rule that applies wins
+    for bits in 1..64u32 {
+        let mode = SmushMode::from_bits_truncate(bits);
+        let settings = smush_settings(mode | SmushMode::SMUSH, false);
+        for (lch, rch, results) in RULE_EXAMPLES {
+            let expected = rules
+                .iter()
+                .zip(results)
+                .filter(|(rule, _)| mode.contains(**rule))
+                .find_map(|(_, result)| result);
+            assert_eq!(
+                smushem(lch, rch, &settings),
+                expected,
+                "{:?} + {:?} with {:?}",
+                lch,
+                rch,
+                mode
+            );
         }
+    }
+}
 
-        result
-            .iter()
-            .map(|art_line| art_line.to_string().replace(self.font.hardblank(), " "))
-            .collect()
+#[test]
+fn smushem_wide() {
+    for smushmode in [SmushMode::SMUSH, SmushMode::SMUSH | SmushMode::EQUAL] {
+        let settings = smush_settings(smushmode, false);
+        assert_eq!(smushem('漢', '漢', &settings), None);
+        assert_eq!(smushem('|', '漢', &settings), None);
+        assert_eq!(smushem(CONTINUATION, '|', &settings), None);
+        assert_eq!(smushem(' ', '漢', &settings), Some('漢'));
+        assert_eq!(smushem(CONTINUATION, ' ', &settings), Some(CONTINUATION));
     }
 }
 
-/// Given a message, font, settings, and maximum width, formats the
-/// message using the font. Returns "art lines", that is, a
-/// Vec<String> that, when printed sequentially, will resemble "lines"
-/// of text. Each String can be multiple lines.
-pub fn art_lines(message: &str, font: &Font, settings: &Settings, max_width: usize) -> Vec<String> {
-    ArtOutput::build(message, font, settings, max_width).lines()
+#[test]
+fn smushem_universal_overlap() {
+    let settings = smush_settings(SmushMode::SMUSH, false);
+    assert_eq!(smushem('/', '|', &settings), Some('|'));
+    assert_eq!(smushem('$', '|', &settings), Some('|'));
+    assert_eq!(smushem('|', '$', &settings), Some('|'));
+    let settings = smush_settings(SmushMode::SMUSH, true);
+    assert_eq!(smushem('/', '|', &settings), Some('/'));
+}
+
+#[test]
+fn smushem_kerning_only_fits() {
+    let settings = smush_settings(SmushMode::KERN, false);
+    assert_eq!(smushem(' ', '|', &settings), Some('|'));
+    assert_eq!(smushem('|', '|', &settings), None);
+}
+
+#[test]
+fn append_aligned() {
+    let settings = smush_settings(SmushMode::empty(), false);
+    let mut tall = glyph(&[vec!['a'], vec!['b'], vec!['c'], vec!['d']]);
+    tall.baseline = 3;
+    let mut short = glyph(&[vec!['x'], vec!['y']]);
+    short.baseline = 1;
+    for (align, expected) in [
+        (Align::Top, ["ax", "by", "c ", "d "]),
+        (Align::Middle, ["a ", "bx", "cy", "d "]),
+        (Align::Baseline, ["a ", "b ", "cx", "dy"]),
+    ] {
+        let mut text = tall.clone();
+        text.append_aligned(&short, align, &settings);
+        assert_eq!(text.display_rows(), expected, "{:?}", align);
+        assert_eq!(text.baseline, 3);
+    }
+
+    // one reaching higher and the other lower makes for a taller text
+    let mut high = glyph(&[vec!['p'], vec!['q']]);
+    high.baseline = 2;
+    let joined = high.append(&short, &settings);
+    assert_eq!(joined.display_rows(), ["p ", "qx", " y"]);
+    assert_eq!(joined.baseline, 2);
+    assert_eq!(high.appended_width(&[&short], &settings), 2);
+
+    // an empty text takes on the shape of what is appended
+    let joined = Text::empty_of_height(5).append(&short, &settings);
+    assert_eq!(joined.display_rows(), ["x", "y"]);
+    assert_eq!(joined.baseline, 1);
+}
+
+#[cfg(test)]
+mod properties {
+    use super::*;
+    use proptest::prelude::*;
+
+    const POOL: &[char] = &[
+        ' ', ' ', ' ', '_', '|', '/', '\\', '[', ']', '{', '}', '(', ')', '<', '>', '$', '#', 'o',
+    ];
+
+    fn sub_character() -> impl Strategy<Value = char> {
+        proptest::sample::select(POOL)
+    }
+
+    fn any_mode() -> impl Strategy<Value = SmushMode> {
+        any::<u32>().prop_map(SmushMode::from_bits_truncate)
+    }
+
+    /// Rows of equal width, like a FIGcharacter
+    fn art(height: usize, width: usize) -> impl Strategy<Value = Vec<Vec<char>>> {
+        proptest::collection::vec(proptest::collection::vec(sub_character(), width), height)
+    }
+
+    fn pair_of_glyphs() -> impl Strategy<Value = (Vec<Vec<char>>, Vec<Vec<char>>)> {
+        (1..5usize, 1..7usize).prop_flat_map(|(h, w)| (art(h, w), art(h, w)))
+    }
+
+    proptest! {
+        #[test]
+        fn space_is_identity(ch in sub_character(), mode in any_mode(), r2l: bool) {
+            let settings = smush_settings(mode, r2l);
+            prop_assert_eq!(smushem(' ', ch, &settings), Some(ch));
+            prop_assert_eq!(smushem(ch, ' ', &settings), Some(ch));
+        }
+
+        #[test]
+        fn smushing_is_never_wider_than_kerning(
+            (left, right) in pair_of_glyphs(),
+            rules in 0..64u32,
+            r2l: bool,
+        ) {
+            let (left, right) = (glyph(&left), glyph(&right));
+            let rules = SmushMode::from_bits_truncate(rules);
+            let smushed = left.append(&right, &smush_settings(rules | SmushMode::SMUSH, r2l));
+            let kerned = left.append(&right, &smush_settings(SmushMode::KERN, r2l));
+            prop_assert!(smushed.width() <= kerned.width());
+            prop_assert!(kerned.width() <= left.width() + right.width());
+        }
+
+        #[test]
+        fn right2left_mirrors_order(
+            (left, right) in pair_of_glyphs(),
+            rules in 1..64u32,
+            kern: bool,
+        ) {
+            let (left, right) = (glyph(&left), glyph(&right));
+            let mode = if kern {
+                SmushMode::KERN
+            } else {
+                SmushMode::from_bits_truncate(rules) | SmushMode::SMUSH
+            };
+            let ltr = left.append(&right, &smush_settings(mode, false));
+            let rtl = right.append(&left, &smush_settings(mode, true));
+            prop_assert_eq!(ltr.art, rtl.art);
+        }
+
+        #[test]
+        fn hardblanks_smush_only_by_their_rule(rules in 1..64u32, other in sub_character()) {
+            let rules = SmushMode::from_bits_truncate(rules);
+            let settings = smush_settings(rules | SmushMode::SMUSH, false);
+            let expected = if rules.contains(SmushMode::HARDBLANK) { Some('$') } else { None };
+            prop_assert_eq!(smushem('$', '$', &settings), expected);
+            if other != ' ' && other != '$' {
+                prop_assert_eq!(smushem('$', other, &settings), None);
+                prop_assert_eq!(smushem(other, '$', &settings), None);
+            }
+        }
+    }
 }
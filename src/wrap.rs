@@ -0,0 +1,105 @@
+use crate::settings::Settings;
+use crate::text::Text;
+
+/// How to choose where lines break between words
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    /// fill each line with as many words as fit before starting the
+    /// next, as figlet does
+    #[default]
+    Greedy,
+    /// choose the breaks that leave the least space at the ends of
+    /// lines, measured as the sum of its square over every line but the
+    /// last. Evens out the right margin, but needs the whole input
+    /// before it can break the first line.
+    MinimumRaggedness,
+}
+
+/// A word laid out and ready to be placed on a line
+pub(crate) struct Word {
+    /// index of the whitespace before the word, if it started a new one
+    pub(crate) separator: Option<usize>,
+    pub(crate) text: Text,
+}
+
+/// Appends `word` to `line`, with a space before it unless the line is
+/// empty
+pub(crate) fn append_word(
+    line: &mut Text,
+    word: &Text,
+    separator: Option<usize>,
+    space: &Text,
+    settings: &Settings,
+) {
+    if !line.is_empty() {
+        match separator {
+            Some(index) => line.append_tagged(space, index, settings),
+            None => line.append_in_place(space, settings),
+        }
+    }
+    line.append_in_place(word, settings);
+}
+
+/// Breaks `words` into lines of at most `max_width` columns with the
+/// least raggedness. See `Wrap::MinimumRaggedness`. Each candidate line
+/// is laid out to measure it, so widths account for smushing across
+/// the spaces between words exactly. A word wider than `max_width` gets
+/// a line of its own. Without a `max_width`, or if they all fit on one
+/// line, all the words share one.
+pub(crate) fn minimum_raggedness(
+    words: &[Word],
+    space: &Text,
+    height: u32,
+    settings: &Settings,
+    max_width: Option<usize>,
+) -> Vec<Text> {
+    let lay_out = |words: &[Word]| {
+        let mut line = Text::empty_of_height(height);
+        for word in words {
+            append_word(&mut line, &word.text, word.separator, space, settings);
+        }
+        line
+    };
+    let whole = lay_out(words);
+    let Some(max_width) = max_width.filter(|&max| whole.line_width(&[], settings) > max) else {
+        return vec![whole];
+    };
+    let n = words.len();
+    // cost of the best breaks for the first i words, and where the last
+    // of their lines starts. Costs are kept in a u128, which holds the
+    // square of any slack, and saturate when summed.
+    let mut best: Vec<Option<(u128, usize)>> = vec![None; n + 1];
+    best[0] = Some((0, 0));
+
+    for start in 0..n {
+        let Some((cost_before, _)) = best[start] else {
+            continue;
+        };
+        let mut line = Text::empty_of_height(height);
+        for (end, word) in words.iter().enumerate().skip(start) {
+            if end > start && line.line_width(&[space, &word.text], settings) > max_width {
+                break;
+            }
+            append_word(&mut line, &word.text, word.separator, space, settings);
+            let slack = max_width.saturating_sub(line.line_width(&[], settings)) as u128;
+            let cost = if end + 1 == n { 0 } else { slack * slack };
+            let total = cost_before.saturating_add(cost);
+            if best[end + 1].is_none_or(|(best_cost, _)| total < best_cost) {
+                best[end + 1] = Some((total, start));
+            }
+        }
+    }
+
+    let mut breaks = vec![];
+    let mut end = n;
+    while end > 0 {
+        let (_, start) = best[end].expect("every word starts a line");
+        breaks.push(start..end);
+        end = start;
+    }
+    breaks
+        .into_iter()
+        .rev()
+        .map(|range| lay_out(&words[range]))
+        .collect()
+}
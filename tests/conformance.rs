@@ -0,0 +1,285 @@
+//! Compares rustlet's output with that of reference figlet 2.2.5 for
+//! every font in fonts/ and each input in data/conformance/cases.txt.
+//!
+//! Fixtures live in data/conformance/<font>/<case>.txt. A combination
+//! without a fixture fails unless it is listed in skip.txt, and those
+//! listed in known-divergences.txt are reported without failing. To
+//! (re)generate fixtures with a figlet binary on the PATH, or the one
+//! named by $FIGLET, run
+//!
+//!     cargo test --test conformance -- --ignored regenerate_fixtures
+//!
+//! which also rewrites skip.txt to list only what figlet couldn't draw.
+
+extern crate rustlet;
+
+use rustlet::art_lines;
+use rustlet::font::read_font_file;
+use rustlet::settings::{Settings, SmushMode};
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DATA: &str = "tests/data/conformance";
+
+/// figlet -I1 prints the version as an integer
+const FIGLET_VERSION: &str = "20205";
+
+struct Case {
+    name: String,
+    width: usize,
+    input: String,
+}
+
+impl Case {
+    fn input_for(&self, font: &str) -> String {
+        self.input.replace("{font}", font)
+    }
+}
+
+fn cases() -> Vec<Case> {
+    let spec = fs::read_to_string(Path::new(DATA).join("cases.txt")).expect("cases.txt");
+    spec.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let mut field = || fields.next().expect("name, width and input");
+            Case {
+                name: field().to_string(),
+                width: field().parse().expect("width"),
+                input: field().to_string(),
+            }
+        })
+        .collect()
+}
+
+fn fonts() -> Vec<String> {
+    let mut fonts: Vec<String> = fs::read_dir("fonts")
+        .expect("fonts directory")
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? == "flf" {
+                Some(path.file_stem()?.to_string_lossy().into_owned())
+            } else {
+                None
+            }
+        })
+        .collect();
+    fonts.sort();
+    fonts
+}
+
+/// The entries of a list of <font>/<case> lines, ignoring comments,
+/// each with the reason given after a tab
+fn listed(name: &str) -> Vec<(String, String)> {
+    let list = fs::read_to_string(Path::new(DATA).join(name)).expect(name);
+    list.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once('\t') {
+            Some((id, reason)) => (id.to_string(), reason.trim().to_string()),
+            None => (line.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Whether `entry` in a list names `id`, where <font>/* stands for
+/// every case
+fn names(entry: &str, id: &str) -> bool {
+    let font = id.split_once('/').map_or(id, |(font, _)| font);
+    entry == id || entry == format!("{}/*", font)
+}
+
+fn is_listed(list: &[(String, String)], id: &str) -> bool {
+    list.iter().any(|(entry, _)| names(entry, id))
+}
+
+const SKIP_HEADER: &str = "\
+# Font/case combinations without a fixture, one per line as
+# <font>/<case>, or <font>/* for all of a font's cases, then a tab and
+# why there is no fixture. Any other combination without a fixture
+# fails the suite, as does an entry without a reason or one that
+# names a combination that has a fixture. regenerate_fixtures rewrites
+# this list.
+";
+
+fn fixture(font: &str, case: &Case) -> PathBuf {
+    Path::new(DATA)
+        .join(font)
+        .join(format!("{}.txt", case.name))
+}
+
+/// Describes a font's horizontal layout, for grouping divergences
+fn layout_mode(settings: &Settings) -> String {
+    let mode = settings.smushmode;
+    if mode.contains(SmushMode::SMUSH) {
+        if settings.is_universal_overlap() {
+            String::from("universal smushing")
+        } else {
+            let rules = [
+                (SmushMode::EQUAL, "equal"),
+                (SmushMode::LOWLINE, "lowline"),
+                (SmushMode::HIERARCHY, "hierarchy"),
+                (SmushMode::PAIR, "pair"),
+                (SmushMode::BIGX, "bigx"),
+                (SmushMode::HARDBLANK, "hardblank"),
+            ];
+            let names: Vec<&str> = rules
+                .iter()
+                .filter(|(rule, _)| mode.contains(*rule))
+                .map(|(_, name)| *name)
+                .collect();
+            format!("smushing ({})", names.join(", "))
+        }
+    } else if mode.contains(SmushMode::KERN) {
+        String::from("kerning")
+    } else {
+        String::from("full width")
+    }
+}
+
+#[test]
+fn conformance() {
+    let cases = cases();
+    let known = listed("known-divergences.txt");
+    let skip = listed("skip.txt");
+    let mut checked = 0;
+    let mut skipped = 0;
+    let mut missing = vec![];
+    let mut stale = vec![];
+    let mut unexpected = vec![];
+    // layout mode -> fonts and cases that differ from figlet
+    let mut divergences: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for font in fonts() {
+        let parsed = read_font_file(format!("fonts/{}.flf", font)).expect("Font not read");
+        for case in &cases {
+            let id = format!("{}/{}", font, case.name);
+            let Ok(expected) = fs::read_to_string(fixture(&font, case)) else {
+                match is_listed(&skip, &id) {
+                    true => skipped += 1,
+                    false => missing.push(id),
+                }
+                continue;
+            };
+            checked += 1;
+            if is_listed(&skip, &id) {
+                stale.push(id.clone());
+            }
+            let input = case.input_for(&font);
+            let actual = art_lines(&input, &parsed, &parsed.settings, case.width).concat();
+            if actual != expected {
+                if !is_listed(&known, &id) {
+                    unexpected.push(id.clone());
+                }
+                divergences
+                    .entry(layout_mode(&parsed.settings))
+                    .or_default()
+                    .push(id);
+            } else if is_listed(&known, &id) {
+                println!("{} now matches figlet, remove it from the known list", id);
+            }
+        }
+    }
+
+    let total = cases.len() * fonts().len();
+    println!(
+        "checked {} of {} font/input combinations, skipped {}",
+        checked, total, skipped
+    );
+    for (mode, failures) in &divergences {
+        println!("{}: {} diverging", mode, failures.len());
+        for failure in failures {
+            println!("    {}", failure);
+        }
+    }
+    let unexplained: Vec<&str> = skip
+        .iter()
+        .filter(|(_, reason)| reason.is_empty())
+        .map(|(entry, _)| entry.as_str())
+        .collect();
+    assert!(
+        unexplained.is_empty(),
+        "no reason given in skip.txt for: {}",
+        unexplained.join(", ")
+    );
+    let unknown: Vec<&str> = skip
+        .iter()
+        .map(|(entry, _)| entry.as_str())
+        .filter(|entry| {
+            !fonts().iter().any(|font| {
+                cases
+                    .iter()
+                    .any(|case| names(entry, &format!("{}/{}", font, case.name)))
+            })
+        })
+        .collect();
+    assert!(
+        unknown.is_empty(),
+        "skip.txt names no font or case: {}",
+        unknown.join(", ")
+    );
+    assert!(
+        stale.is_empty(),
+        "skipped but has a fixture, remove it from skip.txt: {}",
+        stale.join(", ")
+    );
+    assert!(
+        missing.is_empty(),
+        "no fixture in {} for: {}",
+        DATA,
+        missing.join(", ")
+    );
+    assert!(
+        unexpected.is_empty(),
+        "output differs from figlet: {}",
+        unexpected.join(", ")
+    );
+}
+
+#[test]
+#[ignore]
+fn regenerate_fixtures() {
+    let figlet = env::var("FIGLET").unwrap_or_else(|_| String::from("figlet"));
+    let version = Command::new(&figlet)
+        .arg("-I1")
+        .output()
+        .expect("figlet not found, set $FIGLET");
+    let version = String::from_utf8_lossy(&version.stdout);
+    if version.trim() != FIGLET_VERSION {
+        eprintln!(
+            "warning: expected figlet version {}, found {}",
+            FIGLET_VERSION,
+            version.trim()
+        );
+    }
+
+    let mut skip = String::from(SKIP_HEADER);
+    for font in fonts() {
+        for case in cases() {
+            let output = Command::new(&figlet)
+                .args(["-d", "fonts", "-f", &font, "-w"])
+                .arg(case.width.to_string())
+                .arg(case.input_for(&font))
+                .output()
+                .expect("figlet failed");
+            let path = fixture(&font, &case);
+            if !output.status.success() {
+                eprintln!("figlet failed on {}/{}, skipping it", font, case.name);
+                let _ = fs::remove_file(&path);
+                let error = String::from_utf8_lossy(&output.stderr);
+                let reason = match error.lines().next() {
+                    Some(line) if !line.trim().is_empty() => line.trim().to_string(),
+                    _ => format!("figlet exited with {}", output.status),
+                };
+                skip.push_str(&format!("{}/{}\t{}\n", font, case.name, reason));
+                continue;
+            }
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, output.stdout).unwrap();
+        }
+    }
+    fs::write(Path::new(DATA).join("skip.txt"), skip).unwrap();
+}
@@ -1,6 +1,5 @@
 extern crate difference;
 
-use ansi_term;
 use ansi_term::Colour::{Green, Red};
 use ansi_term::Style;
 use difference::{Changeset, Difference};
@@ -23,8 +22,8 @@ pub fn print_diff(cs: &Changeset) -> std::io::Result<()> {
         }
     }
 
-    for i in 0..diffs.len() {
-        match diffs[i] {
+    for diff in diffs {
+        match diff {
             Difference::Same(ref x) => {
                 for line in x.lines() {
                     println!(" {}", line);
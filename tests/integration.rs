@@ -4,10 +4,18 @@ extern crate rustlet;
 
 mod diff;
 
-use rustlet::art_lines;
-use rustlet::font::read_font_file;
+use rustlet::bitmap::{read_bitmap_file, read_glyph_sheet, Pixels};
+use rustlet::border::{Border, Style};
+use rustlet::compose::{hstack, vstack, Padding, Position};
+use rustlet::font::{read_font, read_font_file, write_font, Font, FontError};
+use rustlet::mix::{parse_markup, Mix, Rules};
+use rustlet::render::ArtLine;
+use rustlet::settings::{PrintDirection, Settings, SmushMode};
+use rustlet::text::{ArtOutput, MAX_MARGIN};
+use rustlet::{art_lines, art_stream, render, Align, Overflow, Pixelate, Rendered, Scale, Wrap};
 
 use std::fmt::Write;
+use unicode_width::UnicodeWidthStr;
 
 const FONTS: [&str; 18] = [
     "banner", "big", "block", "bubble", "digital", "ivrit", "lean", "mini", "mnemonic", "script",
@@ -55,3 +63,796 @@ fn test_lean() {
     let expected = include_str!("data/outputs/leanonme.txt");
     assert_diff!(expected, &results);
 }
+
+#[test]
+fn test_render_structured() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let rendered = render("take me home", &parsed, &parsed.settings, 40);
+    assert_eq!(rendered.source(), "take me home");
+    assert_eq!(rendered.art_lines().count(), 2);
+    assert_eq!(rendered.height(), 12);
+    assert!(rendered.width() <= 40);
+
+    let first = rendered.art_line(0).unwrap();
+    assert_eq!(first.text(), "take me");
+    assert_eq!(first.height(), 6);
+
+    let lines = art_lines("take me home", &parsed, &parsed.settings, 40);
+    assert_eq!(rendered.to_lines(), lines);
+    assert_eq!(rendered.to_string(), lines.concat());
+}
+
+#[test]
+fn test_render_spans() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let rendered = render("take me home", &parsed, &parsed.settings, 80);
+    let line = rendered.art_line(0).unwrap();
+    let indices: Vec<usize> = line.spans().iter().map(|span| span.index).collect();
+    assert_eq!(indices, (0..12).collect::<Vec<_>>());
+
+    // 't' and 'a' are smushed together in standard
+    let t = &line.spans()[0];
+    let a = &line.spans()[1];
+    let overlap = t.overlap(a).expect("t and a overlap");
+    assert_eq!(line.hit_test(overlap.start), vec![0, 1]);
+    assert_eq!(line.spans().last().unwrap().columns.end, line.width());
+
+    let (rows, columns) = rendered.locate(8).expect("h is drawn");
+    assert_eq!(rows, 0..6);
+    assert_eq!(rendered.hit_test(2, columns.start + 1), vec![8]);
+}
+
+#[test]
+fn test_stream_matches_art_lines() {
+    for font in ["standard", "ivrit", "banner"] {
+        let parsed = read_font_file(format!("fonts/{}.flf", font)).expect("Font not read");
+        let message = "the quick brown fox jumps over the lazy dog, supercalifragilistic";
+        let expected = art_lines(message, &parsed, &parsed.settings, 50);
+        let streamed: Vec<String> = art_stream(message.chars(), &parsed, &parsed.settings, 50)
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(streamed, expected, "{}", font);
+    }
+}
+
+#[test]
+fn test_stream_hit_test() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let message = "take me  home\nagai\u{301}n";
+    let streamed: Vec<Rendered> =
+        art_stream(message.chars(), &parsed, &parsed.settings, 40).collect();
+    let sources: Vec<&str> = streamed.iter().map(Rendered::source).collect();
+    assert_eq!(sources, ["take me", "home", "agai\u{301}n"]);
+    // each line's spans index into its own source
+    for line in &streamed {
+        let chars: Vec<char> = line.source().chars().collect();
+        for span in line.art_lines().flat_map(|line| line.spans()) {
+            assert!(span.index < chars.len());
+        }
+    }
+    let (rows, columns) = streamed[1].locate(0).expect("h is drawn");
+    assert_eq!(rows, 0..6);
+    assert_eq!(streamed[1].hit_test(2, columns.start + 1), [0]);
+    let (_, columns) = streamed[2].locate(5).expect("n is drawn");
+    assert_eq!(streamed[2].hit_test(3, columns.end - 1), [5]);
+    assert_eq!(streamed[2].source().chars().nth(5), Some('n'));
+}
+
+#[test]
+fn test_newlines_end_lines() {
+    let parsed = read_font_file("fonts/term.flf").expect("Font not read");
+    let streamed: Vec<String> =
+        art_stream("hello\nworld\r\n".chars(), &parsed, &parsed.settings, 80)
+            .map(|line| line.to_string())
+            .collect();
+    assert_eq!(streamed, ["hello\n", "world\n"]);
+    for wrap in [Wrap::Greedy, Wrap::MinimumRaggedness] {
+        let lines = ArtOutput::build("a b\nc", &parsed, &parsed.settings, 80)
+            .wrap(wrap)
+            .lines();
+        assert_eq!(lines, ["a b\n", "c\n"]);
+    }
+}
+
+#[test]
+fn test_deutsch() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let german = art_lines("ÄÖÜ äöüß", &parsed, &parsed.settings, 80);
+    let deutsch = ArtOutput::build("[\\] {|}~", &parsed, &parsed.settings, 80)
+        .deutsch(true)
+        .lines();
+    assert_eq!(deutsch, german);
+    let english = ArtOutput::build("[\\] {|}~", &parsed, &parsed.settings, 80).lines();
+    assert_ne!(english, german);
+}
+
+/// First column drawn for the input character at `index`
+fn column_of(rendered: &Rendered, index: usize) -> usize {
+    rendered.locate(index).expect("character not drawn").1.start
+}
+
+#[test]
+fn test_print_direction() {
+    let parsed = read_font_file("fonts/ivrit.flf").expect("Font not read");
+    assert!(parsed.settings.right2left);
+    let ltr = parsed.settings.with_direction(PrintDirection::LeftToRight);
+    let rtl = parsed.settings.with_direction(PrintDirection::RightToLeft);
+    let font_default = parsed.settings.with_direction(PrintDirection::Font);
+    assert_eq!(font_default, parsed.settings);
+    assert_eq!(
+        art_lines("ab", &parsed, &rtl, 80),
+        art_lines("ab", &parsed, &parsed.settings, 80)
+    );
+
+    let left = render("ab", &parsed, &ltr, 80);
+    assert!(column_of(&left, 0) < column_of(&left, 1));
+    let right = render("ab", &parsed, &rtl, 80);
+    assert!(column_of(&right, 0) > column_of(&right, 1));
+}
+
+#[test]
+fn test_bidi() {
+    let parsed = read_font_file("fonts/ivrit.flf").expect("Font not read");
+    // shin lamed vav final-mem, then a Latin word
+    let message = "\u{5e9}\u{5dc}\u{5d5}\u{5dd} hello";
+    let (shin, mem, h, o) = (0, 3, 5, 9);
+
+    // The font's right-to-left layout also reverses the Latin word
+    let plain = render(message, &parsed, &parsed.settings, 80);
+    assert!(column_of(&plain, h) > column_of(&plain, o));
+
+    let bidi = ArtOutput::build(message, &parsed, &parsed.settings, 80)
+        .bidi(true)
+        .render();
+    assert!(column_of(&bidi, h) < column_of(&bidi, o));
+    assert!(column_of(&bidi, o) < column_of(&bidi, mem));
+    assert!(column_of(&bidi, mem) < column_of(&bidi, shin));
+    assert_eq!(bidi.width(), plain.width());
+
+    // A left-to-right paragraph keeps the Latin text first, even when
+    // the font prints right-to-left
+    let mixed = ArtOutput::build(
+        "hello \u{5e9}\u{5dc}\u{5d5}\u{5dd}",
+        &parsed,
+        &parsed.settings,
+        80,
+    )
+    .bidi(true)
+    .render();
+    assert!(column_of(&mixed, 0) < column_of(&mixed, 6));
+    assert!(column_of(&mixed, 9) < column_of(&mixed, 6));
+
+    // Each paragraph starts a new art line, in its own direction
+    let paragraphs = ArtOutput::build("ab\n\u{5e9}\u{5dc}", &parsed, &parsed.settings, 80)
+        .bidi(true)
+        .render();
+    assert_eq!(paragraphs.art_lines().count(), 2);
+    assert!(column_of(&paragraphs, 0) < column_of(&paragraphs, 1));
+    assert!(column_of(&paragraphs, 3) > column_of(&paragraphs, 4));
+}
+
+#[test]
+fn test_graphemes() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let precomposed = art_lines("Café", &parsed, &parsed.settings, 80);
+    assert_eq!(
+        art_lines("Cafe\u{301}", &parsed, &parsed.settings, 80),
+        precomposed
+    );
+    let chars = "Cafe\u{301} x".chars();
+    let streamed: Vec<String> = art_stream(chars, &parsed, &parsed.settings, 80)
+        .map(|line| line.to_string())
+        .collect();
+    assert_eq!(streamed, art_lines("Café x", &parsed, &parsed.settings, 80));
+
+    // The combining mark belongs to the span of its base character
+    let rendered = render("e\u{301}x", &parsed, &parsed.settings, 80);
+    let line = rendered.art_line(0).unwrap();
+    let indices: Vec<usize> = line.spans().iter().map(|span| span.index).collect();
+    assert_eq!(indices, vec![0, 2]);
+
+    // Fonts without accented letters draw the base character
+    let banner = read_font_file("fonts/banner.flf").expect("Font not read");
+    assert_eq!(
+        art_lines("e\u{301}e\u{302}", &banner, &banner.settings, 80),
+        art_lines("ee", &banner, &banner.settings, 80)
+    );
+}
+
+#[test]
+fn test_wide_glyphs() {
+    let parsed = read_font_file("tests/data/fonts/wide.tlf").expect("Font not read");
+    let message = "Hello World 漢 Hi e\u{301}";
+    for width in 10..40 {
+        let rendered = render(message, &parsed, &parsed.settings, width);
+        let mut widest = 0;
+        for line in rendered.art_lines() {
+            let rows: Vec<usize> = line
+                .to_string()
+                .lines()
+                .map(UnicodeWidthStr::width)
+                .collect();
+            assert!(rows.iter().all(|&row| row == rows[0]), "{:?}", rows);
+            assert_eq!(rows[0], line.width());
+            assert!(rows[0] <= width, "{} wider than {}", rows[0], width);
+            widest = widest.max(rows[0]);
+        }
+        assert_eq!(widest, rendered.width());
+        let streamed: Vec<String> = art_stream(message.chars(), &parsed, &parsed.settings, width)
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(streamed, rendered.to_lines());
+    }
+
+    // Right-to-left lines are justified to the same number of columns
+    let rtl = parsed.settings.with_direction(PrintDirection::RightToLeft);
+    for line in art_lines("漢 Hi", &parsed, &rtl, 20) {
+        for row in line.lines() {
+            assert_eq!(row.width(), 19);
+        }
+    }
+
+    // Wide sub-characters are never split, so the lowlines beneath
+    // them keep their full width too
+    let art = art_lines("AB", &parsed, &parsed.settings, 80).concat();
+    assert_eq!(art, "ＡＢ\n____\n");
+}
+
+#[test]
+fn test_overflow() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let word = "Supercalifragilistic";
+    let modes = [
+        Overflow::Break,
+        Overflow::Hyphenate,
+        Overflow::Shrink,
+        Overflow::Truncate,
+    ];
+    for overflow in modes {
+        for width in 20..60 {
+            let output =
+                ArtOutput::build(word, &parsed, &parsed.settings, width).overflow(overflow);
+            let rendered = output.render();
+            assert!(rendered.width() <= width, "{:?} at {}", overflow, width);
+            let streamed: Vec<String> = art_stream(word.chars(), &parsed, &parsed.settings, width)
+                .overflow(overflow)
+                .map(|line| line.to_string())
+                .collect();
+            assert_eq!(streamed, rendered.to_lines());
+        }
+    }
+    assert_eq!(
+        ArtOutput::build(word, &parsed, &parsed.settings, 30).lines(),
+        ArtOutput::build(word, &parsed, &parsed.settings, 30)
+            .overflow(Overflow::Break)
+            .lines()
+    );
+
+    let hyphenated = ArtOutput::build(word, &parsed, &parsed.settings, 30)
+        .overflow(Overflow::Hyphenate)
+        .render();
+    let texts: Vec<&str> = hyphenated.art_lines().map(|line| line.text()).collect();
+    assert!(texts.len() > 1);
+    let (last, first) = texts.split_last().unwrap();
+    assert!(first.iter().all(|text| text.ends_with('-')));
+    assert!(!last.ends_with('-'));
+    assert_eq!(texts.concat().replace('-', ""), word);
+
+    let truncated = ArtOutput::build("a Supercalifragilistic b", &parsed, &parsed.settings, 30)
+        .overflow(Overflow::Truncate)
+        .render();
+    let texts: Vec<&str> = truncated.art_lines().map(|line| line.text()).collect();
+    assert_eq!(texts.len(), 3);
+    assert!(texts[1].starts_with("Sup") && texts[1].ends_with("..."));
+    assert_eq!(texts[2], "b");
+
+    // banner only kerns, and this is two columns too wide kerned
+    let banner = read_font_file("fonts/banner.flf").expect("Font not read");
+    let broken = ArtOutput::build("abcdef", &banner, &banner.settings, 40).render();
+    assert_eq!(broken.art_lines().count(), 2);
+    let shrunk = ArtOutput::build("abcdef", &banner, &banner.settings, 40)
+        .overflow(Overflow::Shrink)
+        .render();
+    assert_eq!(shrunk.art_lines().count(), 1);
+    assert!(shrunk.width() <= 40);
+}
+
+/// Width of `message` laid out on a single line
+fn single_line_width(message: &str, font: &Font) -> usize {
+    let rendered = render(message, font, &font.settings, 1000);
+    assert_eq!(rendered.art_lines().count(), 1);
+    rendered.width()
+}
+
+/// Sum of the squares of the columns left free at the end of each
+/// line but the last
+fn raggedness(rendered: &Rendered, width: usize) -> usize {
+    let lines: Vec<usize> = rendered.art_lines().map(|line| line.width()).collect();
+    let (_, lines) = lines.split_last().unwrap();
+    lines
+        .iter()
+        .map(|line| (width - line) * (width - line))
+        .sum()
+}
+
+#[test]
+fn test_wrap_exact_width() {
+    for font in [
+        "standard", "small", "slant", "banner", "big", "lean", "term",
+    ] {
+        let parsed = read_font_file(format!("fonts/{}.flf", font)).expect("Font not read");
+        for message in ["Hello World", "a b", "The end."] {
+            // Exactly as wide as the line is fits; one column less doesn't
+            let width = single_line_width(message, &parsed);
+            let fits = render(message, &parsed, &parsed.settings, width);
+            assert_eq!(fits.art_lines().count(), 1, "{} {:?}", font, message);
+            assert_eq!(fits.width(), width);
+            let narrower = render(message, &parsed, &parsed.settings, width - 1);
+            assert_eq!(narrower.art_lines().count(), 2, "{} {:?}", font, message);
+        }
+
+        // Greedy lines are as full as they can be: the next word never
+        // fits on the end
+        let message = "the quick brown fox jumps over the lazy dog";
+        for width in 30..120 {
+            let rendered = render(message, &parsed, &parsed.settings, width);
+            let lines: Vec<ArtLine> = rendered.art_lines().collect();
+            for pair in lines.windows(2) {
+                assert!(pair[0].width() <= width);
+                let next = pair[1].text().split(' ').next().unwrap();
+                let joined = format!("{} {}", pair[0].text(), next);
+                assert!(
+                    single_line_width(&joined, &parsed) > width,
+                    "{:?} fits in {} with {}",
+                    joined,
+                    width,
+                    font
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_wrap_minimum_raggedness() {
+    let parsed = read_font_file("fonts/small.flf").expect("Font not read");
+    let message = "the quick brown fox jumps over the lazy dog and keeps on running";
+    for width in 30..120 {
+        let greedy = render(message, &parsed, &parsed.settings, width);
+        let balanced = ArtOutput::build(message, &parsed, &parsed.settings, width)
+            .wrap(Wrap::MinimumRaggedness)
+            .render();
+        assert!(balanced.width() <= width);
+        assert!(raggedness(&balanced, width) <= raggedness(&greedy, width));
+        let words = |rendered: &Rendered| -> Vec<String> {
+            rendered
+                .art_lines()
+                .flat_map(|line| line.text().split(' ').map(String::from).collect::<Vec<_>>())
+                .collect()
+        };
+        assert_eq!(words(&balanced), words(&greedy));
+        let streamed: Vec<String> = art_stream(message.chars(), &parsed, &parsed.settings, width)
+            .wrap(Wrap::MinimumRaggedness)
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(streamed, balanced.to_lines());
+    }
+
+    let greedy = render(message, &parsed, &parsed.settings, 40);
+    let balanced = ArtOutput::build(message, &parsed, &parsed.settings, 40)
+        .wrap(Wrap::MinimumRaggedness)
+        .render();
+    assert!(raggedness(&balanced, 40) < raggedness(&greedy, 40));
+
+    // Widths too big to square in a usize
+    let unwrapped = render(message, &parsed, &parsed.settings, None);
+    for width in [1usize << 40, usize::MAX] {
+        let balanced = ArtOutput::build(message, &parsed, &parsed.settings, width)
+            .wrap(Wrap::MinimumRaggedness)
+            .render();
+        assert_eq!(balanced.to_lines(), unwrapped.to_lines());
+    }
+}
+
+#[test]
+fn test_no_wrap() {
+    let message = "the quick brown fox jumps over the lazy dog ".repeat(20);
+    let long_word = "Supercalifragilisticexpialidocious".repeat(5);
+    for font in ["standard", "small", "ivrit"] {
+        let parsed = read_font_file(format!("fonts/{}.flf", font)).expect("Font not read");
+        let unwrapped = render(&message, &parsed, &parsed.settings, None);
+        assert_eq!(unwrapped.art_lines().count(), 1);
+        assert!(unwrapped.width() > 1000);
+        let streamed: Vec<String> = art_stream(message.chars(), &parsed, &parsed.settings, None)
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(streamed, unwrapped.to_lines());
+        let balanced = ArtOutput::build(&message, &parsed, &parsed.settings, None)
+            .wrap(Wrap::MinimumRaggedness)
+            .lines();
+        assert_eq!(balanced, unwrapped.to_lines());
+
+        for overflow in [Overflow::Hyphenate, Overflow::Shrink, Overflow::Truncate] {
+            let lines = ArtOutput::build(&long_word, &parsed, &parsed.settings, None)
+                .overflow(overflow)
+                .lines();
+            assert_eq!(
+                lines,
+                art_lines(&long_word, &parsed, &parsed.settings, None)
+            );
+        }
+    }
+
+    // Right-to-left lines aren't padded to any margin
+    let ivrit = read_font_file("fonts/ivrit.flf").expect("Font not read");
+    let padded = render("ab", &ivrit, &ivrit.settings, 80);
+    assert_eq!(padded.width(), 79);
+    let unpadded = render("ab", &ivrit, &ivrit.settings, None);
+    assert!(unpadded.width() < 79);
+    let pad = " ".repeat(79 - unpadded.width());
+    for (padded, unpadded) in padded.to_string().lines().zip(unpadded.to_string().lines()) {
+        assert_eq!(padded, format!("{}{}", pad, unpadded));
+    }
+    // nor to margins wider than any terminal
+    let padded = render("ab", &ivrit, &ivrit.settings, MAX_MARGIN);
+    assert_eq!(padded.width(), MAX_MARGIN - 1);
+    for width in [MAX_MARGIN + 1, usize::MAX] {
+        let huge = render("ab", &ivrit, &ivrit.settings, width);
+        assert_eq!(huge.to_lines(), unpadded.to_lines());
+    }
+}
+
+#[test]
+fn test_bitmap_font() {
+    let bitmap = read_bitmap_file("tests/data/fonts/tiny.bdf").expect("Font not read");
+    assert_eq!((bitmap.height, bitmap.ascent), (8, 6));
+    assert!(bitmap.name.starts_with("-rustlet-tiny-"));
+
+    let font = bitmap.to_font(Pixels::default()).expect("Font not drawn");
+    assert_eq!((font.height(), font.settings.baseline), (8, 6));
+    assert_eq!(font.settings.smushmode, SmushMode::KERN);
+    let lines = render("gi A", &font, &font.settings, 80).to_lines();
+    let expected = [
+        "     #     ###  ",
+        "          #   # ",
+        " ### #    #   # ",
+        "#  # #    ##### ",
+        "#  # #    #   # ",
+        " ### #    #   # ",
+        "   #            ",
+        "###             ",
+    ];
+    assert_eq!(lines, [expected.join("\n") + "\n"]);
+
+    let font = bitmap.to_font(Pixels::HalfBlocks).expect("Font not drawn");
+    assert_eq!((font.height(), font.settings.baseline), (4, 3));
+    let lines = render("gi A", &font, &font.settings, 80).to_lines();
+    let expected = [
+        "     ▀    ▄▀▀▀▄ ",
+        "▄▀▀█ █    █▄▄▄█ ",
+        "▀▄▄█ █    █   █ ",
+        "▄▄▄▀            ",
+    ];
+    assert_eq!(lines, [expected.join("\n") + "\n"]);
+
+    // what the bitmap font lacks is blank, and it writes and reads back
+    assert!(font
+        .get_character(&'Z')
+        .art
+        .iter()
+        .all(|row| row.is_empty()));
+    assert_eq!(read_font(&write_font(&font)), Ok(font));
+}
+
+#[test]
+fn test_glyph_sheet() {
+    let data = std::fs::read("tests/data/fonts/sheet.pbm").unwrap();
+    let sheet = read_glyph_sheet(&data, (4, 5), "Hi!").expect("Sheet not read");
+    assert_eq!((sheet.height, sheet.ascent), (5, 5));
+    assert_eq!(sheet.glyphs.len(), 3);
+
+    let pixels = Pixels::Chars {
+        dark: '@',
+        light: '.',
+    };
+    let font = sheet.to_font(pixels).expect("Font not drawn");
+    let lines = render("H i!", &font, &font.settings, 80).to_lines();
+    let expected = [
+        "@..@   @ @ ",
+        "@..@   . @ ",
+        "@@@@   @ @ ",
+        "@..@   @ . ",
+        "@..@   @ @ ",
+    ];
+    assert_eq!(lines, [expected.join("\n") + "\n"]);
+
+    assert!(read_glyph_sheet(&data, (4, 5), "Hi!?").is_ok());
+    assert_eq!(
+        read_glyph_sheet(&data, (4, 5), "Hi!?x"),
+        Err(FontError::Syntax(
+            "glyph sheet has 4 cells, too few for 5 characters".to_string()
+        ))
+    );
+    assert!(read_glyph_sheet(b"P1 2 2 1 0", (1, 1), "a").is_err());
+}
+
+#[test]
+fn test_pixelate() {
+    let parsed = read_font_file("fonts/colossal.flf").expect("Font not read");
+    let message = "Pixelated text wraps at the terminal width";
+    for (mode, height) in [
+        (Pixelate::HalfBlocks, 6),
+        (Pixelate::Quadrants, 6),
+        (Pixelate::Braille, 3),
+    ] {
+        for width in [20, 40, 80] {
+            let rendered = ArtOutput::build(message, &parsed, &parsed.settings, width)
+                .pixelate(mode)
+                .render();
+            for line in rendered.art_lines() {
+                assert_eq!(line.height(), height);
+                assert!(
+                    line.width() <= width,
+                    "{} wider than {}",
+                    line.width(),
+                    width
+                );
+                for span in line.spans() {
+                    assert!(span.columns.end <= line.width());
+                }
+            }
+            let plain = render(
+                message,
+                &parsed,
+                &parsed.settings,
+                width * mode.cell_size().0,
+            );
+            assert_eq!(rendered.art_lines().count(), plain.art_lines().count());
+
+            let streamed: Vec<String> =
+                art_stream(message.chars(), &parsed, &parsed.settings, width)
+                    .pixelate(mode)
+                    .map(|line| line.to_string())
+                    .collect();
+            assert_eq!(streamed, rendered.to_lines());
+        }
+    }
+
+    let rendered = ArtOutput::build(message, &parsed, &parsed.settings, 80)
+        .pixelate(Pixelate::HalfBlocks)
+        .render();
+    assert!(rendered.to_string().chars().all(|c| " ▀▄█\n".contains(c)));
+    assert!(rendered.to_string().contains('█'));
+}
+
+#[test]
+fn test_scale() {
+    let parsed = read_font_file("fonts/standard.flf").expect("Font not read");
+    let message = "Scaled text still wraps at the given width";
+    for scale in ["2x1", "3x2", "1/2x1/2", "3/2x1"] {
+        let scale: Scale = scale.parse().unwrap();
+        for width in [30, 60, 100] {
+            let rendered = ArtOutput::build(message, &parsed, &parsed.settings, width)
+                .scale(scale)
+                .render();
+            for line in rendered.art_lines() {
+                assert_eq!(line.height(), scale.down.apply(6));
+                assert!(
+                    line.width() <= width,
+                    "{} wider than {}",
+                    line.width(),
+                    width
+                );
+            }
+            let streamed: Vec<String> =
+                art_stream(message.chars(), &parsed, &parsed.settings, width)
+                    .scale(scale)
+                    .map(|line| line.to_string())
+                    .collect();
+            assert_eq!(streamed, rendered.to_lines());
+        }
+    }
+
+    // enlarging repeats each sub-character
+    let plain = render("Hi", &parsed, &parsed.settings, 80).to_string();
+    let doubled = ArtOutput::build("Hi", &parsed, &parsed.settings, 80)
+        .scale("2x1".parse::<Scale>().unwrap())
+        .render()
+        .to_string();
+    let expected: String = plain
+        .chars()
+        .flat_map(|c| if c == '\n' { vec![c] } else { vec![c, c] })
+        .collect();
+    assert_eq!(doubled, expected);
+
+    // and composes with pixelation
+    let rendered = ArtOutput::build("Hi", &parsed, &parsed.settings, 80)
+        .scale("2x2".parse::<Scale>().unwrap())
+        .pixelate(Pixelate::Quadrants)
+        .render();
+    assert_eq!(rendered.height(), 6);
+    assert_eq!(rendered.width(), plain.lines().next().unwrap().len());
+}
+
+#[test]
+fn test_mix() {
+    let banner = read_font_file("fonts/banner.flf").expect("Font not read");
+    let small = read_font_file("fonts/small.flf").expect("Font not read");
+    let standard = read_font_file("fonts/standard.flf").expect("Font not read");
+
+    // a mix of one font lays out as that font does
+    let message = "One font all the way through";
+    let mix = Mix::from_runs(
+        &standard,
+        [("One font ", &standard), ("all the way through", &standard)],
+    );
+    let settings = mix.settings(Rules::Loosest);
+    assert_eq!(settings, standard.settings);
+    assert_eq!(
+        ArtOutput::mixed(&mix, &settings, 40).render(),
+        render(message, &standard, &standard.settings, 40)
+    );
+
+    // fonts are lined up on their baselines: small reaches three rows
+    // less far above its baseline than banner, and as far below
+    let mix = Mix::from_runs(&banner, [("A", &banner), ("b", &small)]);
+    let settings = Settings {
+        smushmode: SmushMode::empty(),
+        ..mix.settings(Rules::Base)
+    };
+    assert_eq!((settings.charheight, settings.baseline), (8, 7));
+    let rendered = ArtOutput::mixed(&mix, &settings, None).render();
+    assert_eq!(rendered.height(), 8);
+    assert_eq!(rendered.source(), "Ab");
+    let rows: Vec<Vec<char>> = rendered
+        .to_string()
+        .lines()
+        .map(|row| row.chars().collect())
+        .collect();
+    let b = rendered.art_line(0).unwrap().spans()[1].columns.clone();
+    let glyph: Vec<String> = small
+        .get_character(&'b')
+        .to_string()
+        .replace('$', " ")
+        .lines()
+        .map(String::from)
+        .collect();
+    let drawn: Vec<String> = rows
+        .iter()
+        .map(|row| row[b.clone()].iter().collect())
+        .collect();
+    let blank = " ".repeat(b.len());
+    assert_eq!(drawn[..3], [blank.clone(), blank.clone(), blank]);
+    assert_eq!(drawn[3..], glyph[..]);
+
+    // or along their tops
+    let mix = Mix::from_runs(&banner, [("A", &banner), ("b", &small)]).align(Align::Top);
+    let settings = Settings {
+        smushmode: SmushMode::empty(),
+        ..mix.settings(Rules::Base)
+    };
+    let rendered = ArtOutput::mixed(&mix, &settings, None).render();
+    let rows: Vec<Vec<char>> = rendered
+        .to_string()
+        .lines()
+        .map(|row| row.chars().collect())
+        .collect();
+    let drawn: Vec<String> = rows
+        .iter()
+        .map(|row| row[b.clone()].iter().collect())
+        .collect();
+    assert_eq!(drawn[..5], glyph[..]);
+    assert!(drawn[5..].iter().all(|row| row.trim().is_empty()));
+
+    // lines wrap with every font's art on them
+    let runs = parse_markup("{font=banner}Mixed {font=small}fonts wrap {font}like one");
+    let fonts = |name: Option<&str>| match name {
+        Some("banner") => &banner,
+        Some("small") => &small,
+        _ => &standard,
+    };
+    let mix = Mix::from_runs(
+        &standard,
+        runs.iter()
+            .map(|(name, text)| (text.as_str(), fonts(*name))),
+    );
+    assert_eq!(mix.text(), "Mixed fonts wrap like one");
+    let settings = mix.settings(Rules::Loosest);
+    assert!(settings.smushmode.contains(SmushMode::KERN) && !settings.is_smush());
+    let rendered = ArtOutput::mixed(&mix, &settings, 40).render();
+    assert!(rendered.art_lines().count() > 1);
+    for line in rendered.art_lines() {
+        assert_eq!(line.height(), 8);
+        assert!(line.width() <= 40);
+    }
+}
+
+#[test]
+fn test_compose() {
+    let standard = read_font_file("fonts/standard.flf").expect("Font not read");
+    let small = read_font_file("fonts/small.flf").expect("Font not read");
+    let title = render("Title", &standard, &standard.settings, None);
+    let subtitle = render("and more", &small, &small.settings, None);
+
+    let stacked = vstack(&[&title, &subtitle], 1, Position::Middle);
+    assert_eq!(stacked.width(), title.width().max(subtitle.width()));
+    assert_eq!(stacked.height(), title.height() + 1 + subtitle.height());
+    assert_eq!(stacked.source(), "Title\nand more");
+    // every character can still be found where it was drawn
+    for (index, ch) in stacked.source().chars().enumerate() {
+        if ch != '\n' && ch != ' ' {
+            let (rows, columns) = stacked.locate(index).expect("character drawn");
+            assert!(stacked.hit_test(rows.start, columns.start).contains(&index));
+        }
+    }
+
+    let columns = hstack(&[&title, &subtitle], 3, Position::End);
+    assert_eq!(columns.width(), title.width() + 3 + subtitle.width());
+    assert_eq!(columns.height(), title.height());
+    let text = columns.to_string();
+    for (row, line) in text.lines().enumerate() {
+        let (left, right) = line.split_at(title.width());
+        assert_eq!(left, title.to_string().lines().nth(row).unwrap());
+        let offset = title.height() - subtitle.height();
+        let expected = match row.checked_sub(offset) {
+            Some(row) => subtitle.to_string().lines().nth(row).unwrap().to_string(),
+            None => " ".repeat(subtitle.width()),
+        };
+        assert_eq!(right, format!("   {}", expected));
+    }
+
+    // padding then overlaying the same art over itself changes nothing
+    let padded = title.pad(Padding::uniform(2));
+    let overlaid = padded.overlay(&title, 2, 2);
+    assert_eq!(overlaid.to_string(), padded.to_string());
+    assert_eq!(overlaid.width(), title.width() + 4);
+}
+
+#[test]
+fn test_border() {
+    let standard = read_font_file("fonts/standard.flf").expect("Font not read");
+    let art = render("Hi", &standard, &standard.settings, None);
+
+    let border = Border::new(Style::Light)
+        .padding(Padding::uniform(1))
+        .title("Hi");
+    let framed = art.framed(&border);
+    assert_eq!(framed.width(), art.width() + 4);
+    assert_eq!(framed.height(), art.height() + 4);
+    let text = framed.to_string();
+    let lines: Vec<_> = text.lines().collect();
+    assert!(lines[0].starts_with("┌─ Hi ─"));
+    assert!(lines[0].ends_with('┐'));
+    assert!(lines[lines.len() - 1].starts_with('└'));
+    for (row, line) in art.to_string().lines().enumerate() {
+        assert_eq!(lines[row + 2], format!("│ {} │", line));
+    }
+    // the art is still found where it was drawn
+    let (rows, columns) = framed.locate(1).expect("character drawn");
+    assert!(framed.hit_test(rows.start + 2, columns.start).contains(&1));
+
+    // a font's own characters make a border as large as its art
+    let framed = art.framed(&Border::from_font(&standard));
+    assert!(framed.width() > art.width() + 2);
+    assert!(framed.height() > art.height() + 2);
+
+    // the border counts against the width lines are wrapped at, given
+    // room for a character inside it
+    let message = "hello world, the quick brown fox";
+    let borders = [
+        (Border::new(Style::Light), 20),
+        (Border::new(Style::Double).padding(Padding::uniform(3)), 20),
+        (
+            Border::from_font(&standard).padding(Padding::uniform(1)),
+            40,
+        ),
+    ];
+    for (border, narrowest) in &borders {
+        for width in [*narrowest, 60, 80] {
+            let inner = border.inner_width(width);
+            assert!(inner < width);
+            let framed = render(message, &standard, &standard.settings, inner).framed(border);
+            assert!(framed.width() <= width, "{} > {}", framed.width(), width);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! FIGfonts as JSON. Run with the `json` feature, which is on by default.
+#![cfg(feature = "json")]
+
+extern crate rustlet;
+
+use rustlet::font::{read_font, read_font_file, write_font, FontError};
+use rustlet::json;
+
+use std::fs;
+
+fn all_fonts() -> Vec<String> {
+    let mut paths: Vec<_> = fs::read_dir("fonts")
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string_lossy().into_owned())
+        .collect();
+    paths.sort();
+    paths.push("tests/data/fonts/wide.tlf".to_string());
+    paths
+}
+
+#[test]
+fn test_json_round_trip() {
+    for path in all_fonts() {
+        let font = read_font_file(&path).expect("Font not read");
+        let written = json::write_font(&font);
+        let mut read = json::read_font(&written).expect(&path);
+        // the comment line count comes from the comment itself
+        read.settings.commentlines = font.settings.commentlines;
+        assert_eq!(read, font, "{}", path);
+        // and back to a FIGfont again
+        assert_eq!(read_font(&write_font(&read)), Ok(font), "{}", path);
+    }
+}
+
+#[test]
+fn test_json_schema() {
+    let schema = serde_json::from_str(json::SCHEMA).unwrap();
+    let validator = jsonschema::validator_for(&schema).expect("schema is valid");
+    for path in all_fonts() {
+        let font = read_font_file(&path).expect("Font not read");
+        let written: serde_json::Value = serde_json::from_str(&json::write_font(&font)).unwrap();
+        let errors: Vec<_> = validator
+            .iter_errors(&written)
+            .map(|e| e.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{}: {:?}", path, errors);
+    }
+
+    let mut font: serde_json::Value = serde_json::from_str(&json::write_font(
+        &read_font_file("fonts/standard.flf").unwrap(),
+    ))
+    .unwrap();
+    font["characters"].as_object_mut().unwrap().remove("65");
+    assert!(!validator.is_valid(&font));
+}
+
+#[test]
+fn test_json_format() {
+    let font = read_font_file("fonts/ivrit.flf").unwrap();
+    let written: serde_json::Value = serde_json::from_str(&json::write_font(&font)).unwrap();
+    assert_eq!(written["header"]["hardblank"], "$");
+    assert_eq!(written["header"]["height"], 6);
+    assert_eq!(written["header"]["print_direction"], 1);
+    let alef = &written["characters"]["1488"];
+    assert_eq!(alef["comment"], "HEBREW LETTER ALEF");
+    assert_eq!(alef["rows"].as_array().unwrap().len(), 6);
+    assert!(written["characters"]["65"].get("comment").is_none());
+}
+
+#[test]
+fn test_json_errors() {
+    let standard = json::write_font(&read_font_file("fonts/standard.flf").unwrap());
+    let mut font: serde_json::Value = serde_json::from_str(&standard).unwrap();
+
+    let mut missing = font.clone();
+    missing["characters"].as_object_mut().unwrap().remove("65");
+    assert_eq!(
+        json::read_font(&missing.to_string()),
+        Err(FontError::Character('A'))
+    );
+
+    let mut short = font.clone();
+    short["characters"]["66"]["rows"]
+        .as_array_mut()
+        .unwrap()
+        .pop();
+    assert_eq!(
+        json::read_font(&short.to_string()),
+        Err(FontError::Character('B'))
+    );
+
+    font["characters"]["55296"] = font["characters"]["65"].clone();
+    assert!(matches!(
+        json::read_font(&font.to_string()),
+        Err(FontError::Syntax(_))
+    ));
+    assert!(matches!(
+        json::read_font("{\"header\": {}}"),
+        Err(FontError::Syntax(_))
+    ));
+}
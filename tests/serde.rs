@@ -0,0 +1,127 @@
+//! Serialization round trips. Run with `cargo test --features serde`.
+#![cfg(feature = "serde")]
+
+extern crate rustlet;
+
+use rustlet::cache::{read_font_dir, write_cache, FontCache};
+use rustlet::font::read_font_file;
+use rustlet::settings::{Settings, SmushMode};
+use rustlet::text::Text;
+use rustlet::{render, Rendered};
+
+use std::io::ErrorKind;
+
+#[test]
+fn test_font_json() {
+    for path in [
+        "fonts/standard.flf",
+        "fonts/ivrit.flf",
+        "tests/data/fonts/wide.tlf",
+    ] {
+        let font = read_font_file(path).expect("Font not read");
+        let json = serde_json::to_string(&font).unwrap();
+        assert_eq!(
+            serde_json::from_str::<rustlet::font::Font>(&json).unwrap(),
+            font
+        );
+        // Characters are written in order, so output is repeatable
+        assert_eq!(serde_json::to_string(&font).unwrap(), json);
+    }
+
+    // a font is checked as it is read back
+    let font = read_font_file("fonts/standard.flf").expect("Font not read");
+    let mut json = serde_json::to_value(&font).unwrap();
+    json["characters"].as_object_mut().unwrap().remove("A");
+    let error = serde_json::from_value::<rustlet::font::Font>(json).unwrap_err();
+    assert!(error.to_string().contains("'A'"));
+
+    // and one with fields this version doesn't know is rejected
+    let mut json = serde_json::to_value(&font).unwrap();
+    json["missing"] = serde_json::json!({});
+    let error = serde_json::from_value::<rustlet::font::Font>(json).unwrap_err();
+    assert!(error.to_string().contains("missing"));
+}
+
+#[test]
+fn test_settings_json() {
+    let settings = Settings {
+        hardblank: '$',
+        charheight: 6,
+        baseline: 5,
+        maxlength: 16,
+        commentlines: 2,
+        right2left: false,
+        smushmode: SmushMode::EQUAL | SmushMode::BIGX | SmushMode::SMUSH,
+    };
+    let json = serde_json::to_value(settings).unwrap();
+    assert_eq!(
+        json["smushmode"],
+        serde_json::json!(["equal", "bigx", "smush"])
+    );
+    assert_eq!(serde_json::from_value::<Settings>(json).unwrap(), settings);
+
+    let unknown = serde_json::json!(["equal", "squash"]);
+    let error = serde_json::from_value::<SmushMode>(unknown).unwrap_err();
+    assert!(error.to_string().contains("squash"));
+}
+
+#[test]
+fn test_render_json() {
+    let font = read_font_file("fonts/standard.flf").expect("Font not read");
+    let rendered = render("Hello, World!", &font, &font.settings, 40);
+    let json = serde_json::to_string(&rendered).unwrap();
+    let decoded: Rendered = serde_json::from_str(&json).unwrap();
+    assert_eq!(decoded, rendered);
+    assert_eq!(decoded.to_string(), rendered.to_string());
+
+    // art lines and spans must lie within the grid
+    let mut json = serde_json::to_value(&rendered).unwrap();
+    json["lines"][0]["rows"]["end"] = serde_json::json!(1000);
+    assert!(serde_json::from_value::<Rendered>(json).is_err());
+    let mut json = serde_json::to_value(&rendered).unwrap();
+    json["lines"][0]["spans"][0]["columns"]["end"] = serde_json::json!(1000);
+    assert!(serde_json::from_value::<Rendered>(json).is_err());
+
+    let glyph = font.get_character(&'H');
+    let json = serde_json::to_value(glyph).unwrap();
+    assert_eq!(json["art"][1], " | | | |");
+    assert_eq!(&serde_json::from_value::<Text>(json).unwrap(), glyph);
+}
+
+#[test]
+fn test_cache() {
+    let fonts = read_font_dir("fonts").expect("Fonts not read");
+    let mut cache = vec![];
+    write_cache(&fonts, &mut cache).unwrap();
+
+    let opened = FontCache::read(&cache[..]).unwrap();
+    assert!(opened.names().eq(fonts.keys().map(String::as_str)));
+    assert_eq!(opened.get("ivrit").unwrap(), fonts["ivrit"]);
+    assert_eq!(opened.fonts().unwrap(), fonts);
+    assert_eq!(
+        opened.get("nonesuch").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    for bad in [&b"not a cache"[..], &cache[..12], &cache[..cache.len() - 1]] {
+        let error = FontCache::read(bad).and_then(|cache| cache.fonts());
+        assert_eq!(error.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    // An entry with a field more than a font has, as a cache from some
+    // other version might, is rejected rather than read without it
+    let cache_of = |entry: &[u8]| {
+        let index = rmp_serde::to_vec(&[("standard", entry.len())]).unwrap();
+        let mut cache = cache[..8].to_vec();
+        cache.extend((index.len() as u32).to_le_bytes());
+        cache.extend(index);
+        cache.extend(entry);
+        FontCache::read(&cache[..]).unwrap().get("standard")
+    };
+    let mut entry = rmp_serde::to_vec(&fonts["standard"]).unwrap();
+    assert_eq!(cache_of(&entry).unwrap(), fonts["standard"]);
+    assert_eq!(entry[0], 0x94, "a font is an array of 4 fields");
+    entry[0] = 0x95;
+    entry.push(0xc0);
+    assert_eq!(cache_of(&entry).unwrap_err().kind(), ErrorKind::InvalidData);
+}